@@ -10,7 +10,7 @@ fn main() {
     let args: Vec<String> = env::args().collect();
 
     if args.len() < 2 {
-        println!("Usage: rust_ray_tracing_cli <image-width>");
+        println!("Usage: rust_ray_tracing_cli <image-width> [samples-per-pixel] [random|cornell]");
         return;
     }
     let image_width = if let Ok(image_width) = args[1].parse::<i32>() {
@@ -29,7 +29,11 @@ fn main() {
         200
     };
 
-    let renderer = Renderer::sample(image_width, samples_per_pixel, 25);
+    let renderer = match args.get(3).map(String::as_str) {
+        None | Some("random") => Renderer::sample(image_width, samples_per_pixel, 25),
+        Some("cornell") => Renderer::cornell_box(image_width, samples_per_pixel, 25),
+        Some(scene) => panic!("Unknown scene '{scene}'. Please use random or cornell"),
+    };
     let height = renderer.image_height;
     let width = renderer.image_width;
 
@@ -24,7 +24,19 @@ pub struct AABB {
 impl AABB {
     pub fn new(min: Vector3<f64>, max: Vector3<f64>) -> Self { AABB { min, max } }
 
-    pub fn hit(&self, ray: &Ray, mut t_min: f64, mut t_max: f64) -> bool {
+    pub fn centroid(&self) -> Vector3<f64> { 0.5 * (self.min + self.max) }
+
+    pub fn surface_area(&self) -> f64 {
+        let d = self.max - self.min;
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+
+    pub fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        self.clip(ray, t_min, t_max).is_some()
+    }
+
+    /// Part of `[t_min, t_max]` where the ray is inside the box.
+    pub fn clip(&self, ray: &Ray, mut t_min: f64, mut t_max: f64) -> Option<(f64, f64)> {
         for a in 0..=2 {
             let inv_d = 1.0 / ray.direction()[a];
             let t0 = (self.min[a] - ray.origin()[a]) * inv_d;
@@ -33,9 +45,9 @@ impl AABB {
             t_min = t_min.max(t0);
             t_max = t_max.min(t1);
             if t_max <= t_min {
-                return false
+                return None
             }
         }
-        true
+        Some((t_min, t_max))
     }
 }
\ No newline at end of file
@@ -0,0 +1,109 @@
+use nalgebra::Vector3;
+
+use super::color::Color;
+
+/// Radiance reaching the camera from rays that escape the scene.
+pub trait Background: Send + Sync {
+    fn color(&self, direction: &Vector3<f64>) -> Color;
+
+    /// Picks a direction with probability proportional to the background's
+    /// brightness, returning it with its solid angle pdf, or `None` when the
+    /// background cannot be importance sampled.
+    fn sample_direction(&self) -> Option<(Vector3<f64>, f64)> {
+        None
+    }
+
+    /// Solid angle pdf of `sample_direction` picking `direction`.
+    fn pdf(&self, _direction: &Vector3<f64>) -> f64 {
+        0.0
+    }
+}
+
+pub struct SolidColor {
+    pub color: Color,
+}
+
+impl Background for SolidColor {
+    fn color(&self, _direction: &Vector3<f64>) -> Color {
+        self.color
+    }
+}
+
+/// Blends from `bottom` to `top` as the direction turns towards `up`.
+pub struct Gradient {
+    pub bottom: Color,
+    pub top: Color,
+    pub up: Vector3<f64>,
+}
+
+impl Background for Gradient {
+    fn color(&self, direction: &Vector3<f64>) -> Color {
+        let t = 0.5 * (direction.normalize().dot(&self.up.normalize()) + 1.0);
+        (1.0 - t) * self.bottom + t * self.top
+    }
+}
+
+/// Preetham, Shirley and Smits (1999) analytic daylight sky for a +Y up
+/// world. Luminance is relative to the zenith, which has luminance 1.
+/// Directions below the horizon see the sky at the horizon.
+pub struct PreethamSky {
+    sun_direction: Vector3<f64>,
+    // Perez coefficients A to E for luminance Y and chromaticities x and y.
+    perez: [[f64; 5]; 3],
+    // Zenith values divided by the Perez function at the zenith, with the
+    // zenith luminance taken as 1.
+    zenith: [f64; 3],
+}
+
+impl PreethamSky {
+    /// `turbidity` ranges from about 2 (very clear) to 10 (hazy).
+    pub fn new(sun_direction: Vector3<f64>, turbidity: f64) -> Self {
+        let sun_direction = sun_direction.normalize();
+        let t = turbidity;
+        let perez = [
+            [0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251, 0.1206 * t - 2.5771, -0.0670 * t + 0.3703],
+            [-0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125, -0.0641 * t - 0.8989, -0.0033 * t + 0.0452],
+            [-0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102, -0.0441 * t - 1.6537, -0.0109 * t + 0.0529],
+        ];
+
+        let theta_s = sun_direction.y.clamp(0.0, 1.0).acos();
+        let cubic = |c: [f64; 4]| c[0] * theta_s.powi(3) + c[1] * theta_s.powi(2) + c[2] * theta_s + c[3];
+        let zenith_x = t * t * cubic([0.00166, -0.00375, 0.00209, 0.0])
+            + t * cubic([-0.02903, 0.06377, -0.03202, 0.00394])
+            + cubic([0.11693, -0.21196, 0.06052, 0.25886]);
+        let zenith_y = t * t * cubic([0.00275, -0.00610, 0.00317, 0.0])
+            + t * cubic([-0.04214, 0.08970, -0.04153, 0.00516])
+            + cubic([0.15346, -0.26756, 0.06670, 0.26688]);
+
+        let mut zenith = [1.0, zenith_x, zenith_y];
+        for (value, coefficients) in zenith.iter_mut().zip(&perez) {
+            *value /= perez_function(coefficients, 1.0, theta_s.cos());
+        }
+        PreethamSky { sun_direction, perez, zenith }
+    }
+}
+
+fn perez_function(coefficients: &[f64; 5], cos_theta: f64, cos_gamma: f64) -> f64 {
+    let [a, b, c, d, e] = *coefficients;
+    let gamma = cos_gamma.clamp(-1.0, 1.0).acos();
+    (1.0 + a * (b / cos_theta).exp()) * (1.0 + c * (d * gamma).exp() + e * cos_gamma * cos_gamma)
+}
+
+impl Background for PreethamSky {
+    fn color(&self, direction: &Vector3<f64>) -> Color {
+        let direction = direction.normalize();
+        let cos_theta = direction.y.max(0.01);
+        let cos_gamma = direction.dot(&self.sun_direction);
+        let [luminance, x, y] = [0, 1, 2].map(|i| self.zenith[i] * perez_function(&self.perez[i], cos_theta, cos_gamma));
+
+        // CIE xyY to XYZ to linear sRGB.
+        let big_x = x / y * luminance;
+        let big_z = (1.0 - x - y) / y * luminance;
+        Color::new(
+            3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z,
+            -0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z,
+            0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z,
+        )
+        .map(|c| c.max(0.0))
+    }
+}
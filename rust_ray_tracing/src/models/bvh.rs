@@ -0,0 +1,108 @@
+use std::cmp::Ordering;
+
+use super::{
+    aabb::{surrounding_box, AABB},
+    hittable::{HitRecord, Hittable},
+    hittable_list::HittableList,
+    ray::Ray,
+};
+
+/// Bounding volume hierarchy over the objects of a `HittableList`.
+///
+/// Objects are split at the median centroid along the axis where the
+/// centroids are spread the most, so a ray only visits the subtrees whose
+/// boxes it crosses instead of testing every object in the list.
+pub struct BvhNode {
+    left: Box<dyn Hittable>,
+    right: Option<Box<dyn Hittable>>,
+    bbox: AABB,
+}
+
+impl BvhNode {
+    /// Panics if `list` is empty or one of its objects has no bounding box
+    /// over `time0..time1`, such as an empty `HittableList`.
+    pub fn new(list: HittableList, time0: f64, time1: f64) -> Self {
+        let objects = list
+            .into_objects()
+            .into_iter()
+            .map(|object| {
+                let bbox = object
+                    .bounding_box(time0, time1)
+                    .expect("No bounding box in BvhNode constructor");
+                (bbox, object)
+            })
+            .collect::<Vec<_>>();
+        assert!(!objects.is_empty(), "Cannot build a BvhNode from an empty HittableList");
+        Self::build(objects)
+    }
+
+    fn build(mut objects: Vec<(AABB, Box<dyn Hittable>)>) -> Self {
+        if objects.len() == 1 {
+            let (bbox, object) = objects.pop().unwrap();
+            return BvhNode { left: object, right: None, bbox };
+        }
+
+        let axis = Self::split_axis(&objects);
+        let mid = objects.len() / 2;
+        objects.select_nth_unstable_by(mid, |(a, _), (b, _)| {
+            a.centroid()[axis].partial_cmp(&b.centroid()[axis]).unwrap_or(Ordering::Equal)
+        });
+        let right_objects = objects.split_off(mid);
+
+        let (left_box, left) = Self::subtree(objects);
+        let (right_box, right) = Self::subtree(right_objects);
+        BvhNode {
+            left,
+            right: Some(right),
+            bbox: surrounding_box(&left_box, &right_box),
+        }
+    }
+
+    fn subtree(mut objects: Vec<(AABB, Box<dyn Hittable>)>) -> (AABB, Box<dyn Hittable>) {
+        if objects.len() == 1 {
+            objects.pop().unwrap()
+        } else {
+            let node = Self::build(objects);
+            (node.bbox, Box::new(node))
+        }
+    }
+
+    fn split_axis(objects: &[(AABB, Box<dyn Hittable>)]) -> usize {
+        let first = objects[0].0.centroid();
+        let (min, max) = objects.iter().fold((first, first), |(min, max), (bbox, _)| {
+            let c = bbox.centroid();
+            (min.inf(&c), max.sup(&c))
+        });
+        (max - min).imax()
+    }
+}
+
+impl Hittable for BvhNode {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        if !self.bbox.hit(ray, t_min, t_max) {
+            return None;
+        }
+        let hit_left = self.left.hit(ray, t_min, t_max);
+        let closest_so_far = hit_left.as_ref().map_or(t_max, |hit| hit.t);
+        let hit_right = self
+            .right
+            .as_ref()
+            .and_then(|right| right.hit(ray, t_min, closest_so_far));
+        hit_right.or(hit_left)
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        if !self.bbox.hit(ray, t_min, t_max) {
+            return 1.0;
+        }
+        let left = self.left.transmittance(ray, t_min, t_max);
+        match &self.right {
+            Some(right) if left > 0.0 => left * right.transmittance(ray, t_min, t_max),
+            _ => left,
+        }
+    }
+
+    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<AABB> {
+        Some(self.bbox)
+    }
+}
@@ -1,5 +1,5 @@
 use nalgebra::Vector3;
-use super::{point::Point, ray::Ray, util::random_vector_in_unit_disk};
+use super::{point::Point, ray::{Ray, RayCone}, util::{random_double_within, random_vector_in_unit_disk}};
 use std::f64;
 
 pub struct Camera {
@@ -10,6 +10,10 @@ pub struct Camera {
     lens_radius: f64,
     u: Vector3<f64>,
     v: Vector3<f64>,
+    viewport_height: f64,
+    pixel_spread: Option<f64>,
+    time0: f64,
+    time1: f64,
 }
 
 impl Camera {
@@ -42,15 +46,39 @@ impl Camera {
             lens_radius,
             u,
             v,
+            viewport_height,
+            pixel_spread: None,
+            time0: 0.0,
+            time1: 0.0,
         }
     }
 
+    /// Gives camera rays a cone covering one pixel of an image
+    /// `image_height` pixels tall, used to filter textures.
+    pub fn with_ray_cones(mut self, image_height: i32) -> Self {
+        self.pixel_spread = Some((self.viewport_height / image_height as f64).atan());
+        self
+    }
+
+    /// Opens the shutter from `time0` to `time1`, so each ray is sent at a
+    /// random time in between.
+    pub fn with_shutter(mut self, time0: f64, time1: f64) -> Self {
+        self.time0 = time0;
+        self.time1 = time1;
+        self
+    }
+
     pub fn get_ray(&self, s: f64, t: f64) -> Ray {
         let rd = self.lens_radius * random_vector_in_unit_disk();
         let offset = self.u * rd.x + self.v * rd.y;
-        Ray::new(
+        let ray = Ray::new(
             self.origin + offset,
             self.lower_left_corner + s * self.horizontal + t * self.vertical - self.origin - offset,
-        )
+            if self.time1 > self.time0 { random_double_within(self.time0, self.time1) } else { self.time0 },
+        );
+        match self.pixel_spread {
+            Some(spread) => ray.with_cone(RayCone { width: 0.0, spread }),
+            None => ray,
+        }
     }
 }
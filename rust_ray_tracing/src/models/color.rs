@@ -1,2 +1,7 @@
 use nalgebra::Vector3;
-pub type Color = Vector3<f64>;
\ No newline at end of file
+pub type Color = Vector3<f64>;
+
+/// Relative luminance of a linear sRGB color.
+pub fn luminance(color: &Color) -> f64 {
+    0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
+}
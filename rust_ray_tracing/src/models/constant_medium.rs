@@ -0,0 +1,80 @@
+use std::sync::Arc;
+
+use nalgebra::Vector3;
+
+use super::{
+    aabb::AABB,
+    hittable::{HitRecord, Hittable},
+    material::Material,
+    ray::Ray,
+    util::random_double,
+};
+
+/// Fog or smoke of uniform density filling a closed, convex boundary. Rays
+/// travel an exponentially distributed distance inside before scattering
+/// off `phase_function`, usually an `Isotropic` material.
+pub struct ConstantMedium<H: Hittable> {
+    boundary: H,
+    neg_inv_density: f64,
+    phase_function: Arc<dyn Material>,
+}
+
+impl<H: Hittable> ConstantMedium<H> {
+    pub fn new(boundary: H, density: f64, phase_function: Arc<dyn Material>) -> Self {
+        ConstantMedium { boundary, neg_inv_density: -1.0 / density, phase_function }
+    }
+
+    /// Part of `[t_min, t_max]` inside the boundary. Finds where the whole
+    /// line enters and leaves it, then clips that span to the interval.
+    fn span(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+        let entry = self.boundary.hit(ray, f64::NEG_INFINITY, f64::INFINITY)?;
+        let exit = self.boundary.hit(ray, entry.t + 0.0001, f64::INFINITY)?;
+        let t_enter = entry.t.max(t_min);
+        let t_exit = exit.t.min(t_max);
+        if t_enter >= t_exit {
+            return None;
+        }
+        Some((t_enter, t_exit))
+    }
+}
+
+impl<H: Hittable> Hittable for ConstantMedium<H> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let (t_enter, t_exit) = self.span(ray, t_min, t_max)?;
+        let ray_length = ray.direction().magnitude();
+        let distance_inside = (t_exit - t_enter) * ray_length;
+        let hit_distance = self.neg_inv_density * random_double().ln();
+        if hit_distance > distance_inside {
+            return None;
+        }
+
+        let t = t_enter + hit_distance / ray_length;
+        // Normal and orientation mean nothing inside a volume.
+        Some(HitRecord {
+            t,
+            p: ray.at(t),
+            u: 0.0,
+            v: 0.0,
+            normal: Vector3::x(),
+            front_face: true,
+            material: self.phase_function.clone(),
+            dpdu: Vector3::zeros(),
+            dpdv: Vector3::zeros(),
+        })
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<AABB> {
+        self.boundary.bounding_box(time0, time1)
+    }
+
+    /// Beer-Lambert attenuation, exact for a constant density.
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        match self.span(ray, t_min, t_max) {
+            Some((t_enter, t_exit)) => {
+                let distance_inside = (t_exit - t_enter) * ray.direction().magnitude();
+                (distance_inside / self.neg_inv_density).exp()
+            }
+            None => 1.0,
+        }
+    }
+}
@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use nalgebra::Vector3;
 
-use super::{hittable_list::HittableList, rect::{AARect, Plane}, hittable::{FlipNormals, Hittable, HitRecord}, ray::Ray, aabb::AABB, material::Material};
+use super::{hittable_list::HittableList, rect::{AARect, Plane}, hittable::{FlipNormals, Hittable, HitRecord}, ray::Ray, aabb::AABB, material::Material, point::Point};
 
 
 pub struct Cube {
@@ -32,4 +32,12 @@ impl Hittable for Cube {
     fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<AABB> {
         Some(AABB { min: self.p_min, max: self.p_max })
     }
-}
\ No newline at end of file
+
+    fn pdf_value(&self, origin: &Point, direction: &Vector3<f64>) -> f64 {
+        self.sides.pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: &Point) -> Vector3<f64> {
+        self.sides.random(origin)
+    }
+}
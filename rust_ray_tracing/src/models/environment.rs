@@ -0,0 +1,409 @@
+use std::{f64::consts::PI, fs, path::Path};
+
+use nalgebra::Vector3;
+
+use super::{
+    background::Background,
+    color::{luminance, Color},
+    load_error::LoadError,
+    util::random_double,
+};
+
+/// Equirectangular environment map lit around a +Y up world. Row 0 of the
+/// image is the zenith and the image wraps once around the horizon.
+///
+/// Directions can be importance sampled with probability proportional to
+/// the luminance of their texel, so small bright regions like the sun are
+/// found by explicit sampling instead of by chance.
+pub struct EnvironmentMap {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+    rotation: f64,
+    intensity: f64,
+    row_cdf: Vec<f64>,
+    column_cdfs: Vec<f64>,
+    // Density of each texel over the unit square of (u, v).
+    texel_pdf: Vec<f64>,
+}
+
+impl EnvironmentMap {
+    /// Loads a Radiance `.hdr` (RGBE) or `.pfm` image. `rotation` turns the
+    /// map around +Y in degrees and `intensity` scales its radiance.
+    pub fn load(path: impl AsRef<Path>, rotation: f64, intensity: f64) -> Result<Self, LoadError> {
+        let path = path.as_ref();
+        let bytes = fs::read(path)?;
+        let decoded = if bytes.starts_with(b"#?") {
+            decode_rgbe(&bytes)
+        } else if bytes.starts_with(b"PF") || bytes.starts_with(b"Pf") {
+            decode_pfm(&bytes)
+        } else {
+            Err("not a Radiance HDR or PFM image".to_string())
+        };
+        let (width, height, pixels) = decoded.map_err(|message| LoadError::Format {
+            file: path.display().to_string(),
+            message,
+        })?;
+        Ok(Self::new(width, height, pixels, rotation, intensity))
+    }
+
+    /// Builds a map from linear RGB texels stored row by row, top row first.
+    pub fn new(width: usize, height: usize, pixels: Vec<Color>, rotation: f64, intensity: f64) -> Self {
+        assert!(width > 0 && height > 0, "An environment map needs at least one texel");
+        assert_eq!(pixels.len(), width * height, "Expected width * height texels");
+
+        // Texels near the poles cover less solid angle, weight them by sin(theta).
+        let weights = (0..height)
+            .flat_map(|row| {
+                let sin_theta = (PI * (row as f64 + 0.5) / height as f64).sin();
+                pixels[row * width..(row + 1) * width]
+                    .iter()
+                    .map(move |color| luminance(color) * sin_theta)
+            })
+            .collect::<Vec<_>>();
+
+        let mut column_cdfs = Vec::with_capacity(height * (width + 1));
+        let mut row_weights = Vec::with_capacity(height);
+        for row in weights.chunks(width) {
+            let (cdf, total) = cumulative(row);
+            column_cdfs.extend(cdf);
+            row_weights.push(total);
+        }
+        let (row_cdf, total) = cumulative(&row_weights);
+        let texel_count = (width * height) as f64;
+        let texel_pdf = if total > 0.0 {
+            weights.iter().map(|w| w / total * texel_count).collect()
+        } else {
+            vec![1.0; width * height]
+        };
+
+        EnvironmentMap {
+            width,
+            height,
+            pixels,
+            rotation: rotation.to_radians(),
+            intensity,
+            row_cdf,
+            column_cdfs,
+            texel_pdf,
+        }
+    }
+
+    fn texel_index(&self, direction: &Vector3<f64>) -> (usize, f64) {
+        let direction = direction.normalize();
+        let theta = direction.y.clamp(-1.0, 1.0).acos();
+        let phi = (direction.z.atan2(direction.x) + self.rotation).rem_euclid(2.0 * PI);
+        let column = ((phi / (2.0 * PI) * self.width as f64) as usize).min(self.width - 1);
+        let row = ((theta / PI * self.height as f64) as usize).min(self.height - 1);
+        (row * self.width + column, theta.sin())
+    }
+}
+
+impl Background for EnvironmentMap {
+    fn color(&self, direction: &Vector3<f64>) -> Color {
+        let (index, _) = self.texel_index(direction);
+        self.intensity * self.pixels[index]
+    }
+
+    fn sample_direction(&self) -> Option<(Vector3<f64>, f64)> {
+        let row = sample_cdf(&self.row_cdf, random_double());
+        let column_cdf = &self.column_cdfs[row * (self.width + 1)..(row + 1) * (self.width + 1)];
+        let column = sample_cdf(column_cdf, random_double());
+
+        let u = (column as f64 + random_double()) / self.width as f64;
+        let v = (row as f64 + random_double()) / self.height as f64;
+        let theta = v * PI;
+        let phi = u * 2.0 * PI - self.rotation;
+        let sin_theta = theta.sin();
+        if sin_theta <= 0.0 {
+            return None;
+        }
+        let direction = Vector3::new(sin_theta * phi.cos(), theta.cos(), sin_theta * phi.sin());
+        let pdf = self.texel_pdf[row * self.width + column] / (2.0 * PI * PI * sin_theta);
+        Some((direction, pdf))
+    }
+
+    fn pdf(&self, direction: &Vector3<f64>) -> f64 {
+        let (index, sin_theta) = self.texel_index(direction);
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+        self.texel_pdf[index] / (2.0 * PI * PI * sin_theta)
+    }
+}
+
+/// Normalized cumulative distribution of `weights` with a leading zero,
+/// uniform when every weight is zero, and the sum of the weights.
+fn cumulative(weights: &[f64]) -> (Vec<f64>, f64) {
+    let total: f64 = weights.iter().sum();
+    let mut cdf = Vec::with_capacity(weights.len() + 1);
+    cdf.push(0.0);
+    let mut running = 0.0;
+    for (i, weight) in weights.iter().enumerate() {
+        running += weight;
+        cdf.push(if total > 0.0 { running / total } else { (i + 1) as f64 / weights.len() as f64 });
+    }
+    (cdf, total)
+}
+
+fn sample_cdf(cdf: &[f64], u: f64) -> usize {
+    // Empty entries (zero weight) are skipped because their CDF does not grow.
+    let i = cdf.partition_point(|&c| c <= u);
+    i.saturating_sub(1).min(cdf.len() - 2)
+}
+
+fn decode_rgbe(bytes: &[u8]) -> Result<(usize, usize, Vec<Color>), String> {
+    let mut position = 0;
+    let mut next_line = || {
+        let start = position;
+        while position < bytes.len() && bytes[position] != b'\n' {
+            position += 1;
+        }
+        let line = String::from_utf8_lossy(&bytes[start..position]).trim().to_string();
+        position += 1;
+        line
+    };
+    loop {
+        let line = next_line();
+        if line.is_empty() {
+            break;
+        }
+        if line.starts_with("FORMAT=") && line != "FORMAT=32-bit_rle_rgbe" {
+            return Err(format!("unsupported {line}"));
+        }
+    }
+    let resolution = next_line();
+    let tokens = resolution.split_whitespace().collect::<Vec<_>>();
+    let (height, width) = match tokens.as_slice() {
+        ["-Y", height, "+X", width] => (
+            height.parse::<usize>().map_err(|_| "invalid height".to_string())?,
+            width.parse::<usize>().map_err(|_| "invalid width".to_string())?,
+        ),
+        _ => return Err(format!("unsupported resolution line '{resolution}'")),
+    };
+    if width == 0 || height == 0 {
+        return Err("image has no pixels".to_string());
+    }
+
+    let mut data = &bytes[position.min(bytes.len())..];
+    let truncated = || "unexpected end of file".to_string();
+    // A run covers at most 127 pixels of one channel in 2 bytes, so no
+    // scanline takes less than width / 16 bytes. Reject sizes the data
+    // cannot hold before allocating for them.
+    let pixel_count = width.checked_mul(height).ok_or_else(truncated)?;
+    if height > 0 && data.len() < width / 16 {
+        return Err(truncated());
+    }
+    let mut pixels = Vec::with_capacity(pixel_count.min(data.len()));
+    let mut scanline = vec![0u8; 4 * width];
+    for _ in 0..height {
+        let is_rle = (8..0x8000).contains(&width) && data.len() >= 4 && data[0] == 2 && data[1] == 2 && data[2] & 0x80 == 0;
+        if is_rle {
+            if ((data[2] as usize) << 8 | data[3] as usize) != width {
+                return Err("scanline width mismatch".to_string());
+            }
+            data = &data[4..];
+            // Each channel of the scanline is run-length encoded separately.
+            for channel in 0..4 {
+                let mut x = 0;
+                while x < width {
+                    let (&count, rest) = data.split_first().ok_or_else(truncated)?;
+                    if count > 128 {
+                        let run = (count - 128) as usize;
+                        let &value = rest.first().ok_or_else(truncated)?;
+                        if x + run > width {
+                            return Err("run exceeds scanline".to_string());
+                        }
+                        for i in x..x + run {
+                            scanline[4 * i + channel] = value;
+                        }
+                        x += run;
+                        data = &rest[1..];
+                    } else {
+                        let count = count as usize;
+                        if count == 0 || x + count > width || rest.len() < count {
+                            return Err("invalid literal run".to_string());
+                        }
+                        for (i, &value) in rest[..count].iter().enumerate() {
+                            scanline[4 * (x + i) + channel] = value;
+                        }
+                        x += count;
+                        data = &rest[count..];
+                    }
+                }
+            }
+        } else {
+            if data.len() < 4 * width {
+                return Err(truncated());
+            }
+            scanline.copy_from_slice(&data[..4 * width]);
+            data = &data[4 * width..];
+        }
+        pixels.extend(scanline.chunks(4).map(|rgbe| {
+            if rgbe[3] == 0 {
+                Color::zeros()
+            } else {
+                let scale = 2f64.powi(rgbe[3] as i32 - 136);
+                Color::new(rgbe[0] as f64 * scale, rgbe[1] as f64 * scale, rgbe[2] as f64 * scale)
+            }
+        }));
+    }
+    Ok((width, height, pixels))
+}
+
+fn decode_pfm(bytes: &[u8]) -> Result<(usize, usize, Vec<Color>), String> {
+    // Header: magic, width, height and scale separated by whitespace, then a
+    // single whitespace character before the raster.
+    let mut position = 0;
+    let mut tokens = Vec::new();
+    while tokens.len() < 4 {
+        while position < bytes.len() && bytes[position].is_ascii_whitespace() {
+            position += 1;
+        }
+        let start = position;
+        while position < bytes.len() && !bytes[position].is_ascii_whitespace() {
+            position += 1;
+        }
+        if start == position {
+            return Err("truncated header".to_string());
+        }
+        tokens.push(String::from_utf8_lossy(&bytes[start..position]).to_string());
+    }
+    position += 1;
+
+    let channels = if tokens[0] == "PF" { 3 } else { 1 };
+    let width: usize = tokens[1].parse().map_err(|_| "invalid width".to_string())?;
+    let height: usize = tokens[2].parse().map_err(|_| "invalid height".to_string())?;
+    let scale: f64 = tokens[3].parse().map_err(|_| "invalid scale".to_string())?;
+    if width == 0 || height == 0 {
+        return Err("image has no pixels".to_string());
+    }
+    let little_endian = scale < 0.0;
+
+    let raster = bytes.get(position..).unwrap_or(&[]);
+    let raster_size = [channels, width, height].iter().try_fold(4usize, |size, &n| size.checked_mul(n));
+    if raster_size.is_none_or(|size| raster.len() < size) {
+        return Err("unexpected end of file".to_string());
+    }
+    let values = raster
+        .chunks_exact(4)
+        .take(channels * width * height)
+        .map(|b| {
+            let b = [b[0], b[1], b[2], b[3]];
+            (if little_endian { f32::from_le_bytes(b) } else { f32::from_be_bytes(b) }) as f64
+        })
+        .collect::<Vec<_>>();
+
+    // Rows are stored bottom to top.
+    let mut pixels = Vec::with_capacity(width * height);
+    for row in (0..height).rev() {
+        let row_values = &values[row * width * channels..(row + 1) * width * channels];
+        pixels.extend(row_values.chunks(channels).map(|c| {
+            if channels == 3 { Color::new(c[0], c[1], c[2]) } else { Color::new(c[0], c[0], c[0]) }
+        }));
+    }
+    Ok((width, height, pixels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::test_util::TempFile;
+
+    fn rgbe_header(width: usize, height: usize) -> Vec<u8> {
+        format!("#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {height} +X {width}\n").into_bytes()
+    }
+
+    #[test]
+    fn decodes_flat_rgbe() {
+        let mut bytes = rgbe_header(3, 2);
+        for pixel in 0..6u8 {
+            // Exponent 129 scales mantissas by 2^-7.
+            bytes.extend([128, 16 * pixel, 0, 129]);
+        }
+        let (width, height, pixels) = decode_rgbe(&bytes).unwrap();
+        assert_eq!((width, height), (3, 2));
+        for (pixel, color) in pixels.iter().enumerate() {
+            assert_eq!(*color, Color::new(1.0, pixel as f64 / 8.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn decodes_run_length_encoded_rgbe() {
+        let mut bytes = rgbe_header(8, 2);
+        for row in 0..2u8 {
+            bytes.extend([2, 2, 0, 8]);
+            // Red is a run, green literal values, blue and exponent runs.
+            bytes.extend([128 + 8, 128 >> row]);
+            bytes.push(8);
+            bytes.extend((0..8).map(|x| 16 * x));
+            bytes.extend([128 + 8, 0]);
+            bytes.extend([128 + 8, 129]);
+        }
+        let (width, height, pixels) = decode_rgbe(&bytes).unwrap();
+        assert_eq!((width, height), (8, 2));
+        for (i, color) in pixels.iter().enumerate() {
+            let (row, x) = (i / 8, i % 8);
+            assert_eq!(*color, Color::new(if row == 0 { 1.0 } else { 0.5 }, x as f64 / 8.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn decodes_pfm_bottom_row_first() {
+        let mut bytes = b"PF\n2 2\n-1.0\n".to_vec();
+        for value in [1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0] {
+            bytes.extend(value.to_le_bytes());
+        }
+        let (width, height, pixels) = decode_pfm(&bytes).unwrap();
+        assert_eq!((width, height), (2, 2));
+        assert_eq!(
+            pixels,
+            vec![
+                Color::new(7.0, 8.0, 9.0),
+                Color::new(10.0, 11.0, 12.0),
+                Color::new(1.0, 2.0, 3.0),
+                Color::new(4.0, 5.0, 6.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn decodes_big_endian_grayscale_pfm() {
+        let mut bytes = b"Pf\n1 2\n1.0\n".to_vec();
+        for value in [0.25f32, 0.75] {
+            bytes.extend(value.to_be_bytes());
+        }
+        let (_, _, pixels) = decode_pfm(&bytes).unwrap();
+        assert_eq!(pixels, vec![Color::new(0.75, 0.75, 0.75), Color::new(0.25, 0.25, 0.25)]);
+    }
+
+    #[test]
+    fn rejects_sizes_beyond_the_data() {
+        let huge = b"PF\n99999999999 99999999999\n-1.0\n0000";
+        assert_eq!(decode_pfm(huge).err().unwrap(), "unexpected end of file");
+        let mut huge = rgbe_header(99999999999, 99999999999);
+        huge.extend([0; 16]);
+        assert_eq!(decode_rgbe(&huge).err().unwrap(), "unexpected end of file");
+    }
+
+    #[test]
+    fn rejects_empty_images() {
+        assert_eq!(decode_pfm(b"PF\n0 4\n-1.0\n").err().unwrap(), "image has no pixels");
+        assert_eq!(decode_rgbe(&rgbe_header(4, 0)).err().unwrap(), "image has no pixels");
+        let file = TempFile::new("empty.pfm", b"Pf\n3 0\n1.0\n");
+        assert!(matches!(EnvironmentMap::load(file.path(), 0.0, 1.0), Err(LoadError::Format { .. })));
+    }
+
+    #[test]
+    fn sampled_pdf_matches_pdf() {
+        let pixels = (0..32)
+            .map(|i| if i == 13 { Color::new(50.0, 40.0, 30.0) } else { Color::new(0.1, 0.2, (i % 5) as f64 * 0.1) })
+            .collect();
+        let map = EnvironmentMap::new(8, 4, pixels, 30.0, 2.0);
+        for _ in 0..1000 {
+            let (direction, pdf) = map.sample_direction().unwrap();
+            assert!(pdf > 0.0);
+            assert!((pdf - map.pdf(&direction)).abs() <= 1e-9 * pdf, "{pdf} vs {}", map.pdf(&direction));
+        }
+    }
+}
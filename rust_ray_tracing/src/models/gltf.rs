@@ -0,0 +1,275 @@
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use gltf::{buffer, camera::Projection, mesh::Mode, Node};
+use nalgebra::{Matrix3, Matrix4, Vector3, Vector4};
+
+use super::{
+    camera::Camera,
+    color::Color,
+    hittable_list::HittableList,
+    load_error::LoadError,
+    material::{Dielectric, DiffuseLight, Lambertian, Material, Metal},
+    mesh::{MeshData, Shading, TriangleMesh},
+    point::Point,
+};
+
+/// Objects and camera of a glTF scene.
+pub struct GltfScene {
+    pub world: HittableList,
+    pub camera: Option<Camera>,
+}
+
+/// Loads the default scene of a `.gltf` or `.glb` file. Buffers are read
+/// from the file itself, data URIs or files next to it, never fetched over
+/// the network.
+///
+/// Node transforms are baked into one `TriangleMesh` per mesh node. The
+/// first perspective camera found becomes the scene camera, rendered with
+/// `aspect_ratio` as glTF leaves it to the viewport. Metallic-roughness
+/// materials map to `DiffuseLight` when emissive, `Dielectric` when
+/// transmissive (`KHR_materials_transmission`), `Metal` when mostly
+/// metallic (roughness as fuzz) and `Lambertian` otherwise. Alpha coverage
+/// is not supported: `BLEND` and `MASK` surfaces render opaque with their
+/// base color.
+///
+/// Images are not loaded, so materials only use their color factors and
+/// ignore textures, and the camera's own `aspectRatio` is ignored. Mesh
+/// primitives without positions, drawing points or lines, or with indices
+/// past their vertices are reported as `LoadError::Format`.
+pub fn load_gltf(path: impl AsRef<Path>, aspect_ratio: f64) -> Result<GltfScene, LoadError> {
+    let path = path.as_ref();
+    let (document, buffers, _images) = gltf::import(path).map_err(|error| LoadError::Format {
+        file: path.display().to_string(),
+        message: error.to_string(),
+    })?;
+
+    let mut loader = SceneLoader {
+        file: path.display().to_string(),
+        buffers: &buffers,
+        aspect_ratio,
+        materials: HashMap::new(),
+        scene: GltfScene { world: HittableList::default(), camera: None },
+    };
+    if let Some(scene) = document.default_scene().or_else(|| document.scenes().next()) {
+        for node in scene.nodes() {
+            loader.visit(&node, &Matrix4::identity())?;
+        }
+    }
+    Ok(loader.scene)
+}
+
+struct SceneLoader<'a> {
+    file: String,
+    buffers: &'a [buffer::Data],
+    aspect_ratio: f64,
+    materials: HashMap<Option<usize>, Arc<dyn Material>>,
+    scene: GltfScene,
+}
+
+impl SceneLoader<'_> {
+    fn visit(&mut self, node: &Node, parent: &Matrix4<f64>) -> Result<(), LoadError> {
+        let local = node.transform().matrix();
+        let transform = parent * Matrix4::from_fn(|row, column| local[column][row] as f64);
+
+        if let Some(mesh) = node.mesh() {
+            if let Some(mesh) = self.mesh(&mesh, &transform)? {
+                self.scene.world.push(mesh);
+            }
+        }
+        if let (Some(camera), None) = (node.camera(), &self.scene.camera) {
+            if let Projection::Perspective(perspective) = camera.projection() {
+                self.scene.camera = Some(self.camera(perspective.yfov() as f64, &transform));
+            }
+        }
+        for child in node.children() {
+            self.visit(&child, &transform)?;
+        }
+        Ok(())
+    }
+
+    fn error(&self, mesh: &gltf::Mesh, primitive: &gltf::Primitive, message: &str) -> LoadError {
+        LoadError::Format {
+            file: self.file.clone(),
+            message: format!("mesh {} primitive {}: {message}", mesh.index(), primitive.index()),
+        }
+    }
+
+    fn mesh(&mut self, mesh: &gltf::Mesh, transform: &Matrix4<f64>) -> Result<Option<TriangleMesh>, LoadError> {
+        let normal_matrix = transform
+            .fixed_slice::<3, 3>(0, 0)
+            .try_inverse()
+            .map(|inverse| inverse.transpose())
+            .unwrap_or_else(Matrix3::identity);
+        let mut data = MeshData::default();
+        let mut materials = Vec::new();
+        let mut has_normals = true;
+
+        for primitive in mesh.primitives() {
+            let reader = primitive.reader(|buffer| Some(&self.buffers[buffer.index()]));
+            let positions = match reader.read_positions() {
+                Some(positions) => positions.collect::<Vec<_>>(),
+                None => return Err(self.error(mesh, &primitive, "no POSITION attribute")),
+            };
+            let base = data.positions.len();
+            let vertex_count = positions.len();
+            let indices = reader
+                .read_indices()
+                .map(|indices| indices.into_u32().map(|i| i as usize).collect::<Vec<_>>())
+                .unwrap_or_else(|| (0..vertex_count).collect());
+            let triangles = match primitive.mode() {
+                Mode::Triangles => indices.chunks_exact(3).map(|t| [t[0], t[1], t[2]]).collect::<Vec<_>>(),
+                Mode::TriangleStrip => (2..indices.len())
+                    .map(|i| if i % 2 == 0 {
+                        [indices[i - 2], indices[i - 1], indices[i]]
+                    } else {
+                        [indices[i - 1], indices[i - 2], indices[i]]
+                    })
+                    .collect(),
+                Mode::TriangleFan => (2..indices.len()).map(|i| [indices[0], indices[i - 1], indices[i]]).collect(),
+                mode => return Err(self.error(mesh, &primitive, &format!("unsupported mode {mode:?}"))),
+            };
+            if let Some(index) = triangles.iter().flatten().find(|&&i| i >= vertex_count) {
+                let message = format!("index {index} but there are only {vertex_count} vertices");
+                return Err(self.error(mesh, &primitive, &message));
+            }
+
+            data.positions.extend(positions.iter().map(|p| {
+                let p = transform * Vector4::new(p[0] as f64, p[1] as f64, p[2] as f64, 1.0);
+                Point::new(p.x, p.y, p.z)
+            }));
+            match reader.read_normals() {
+                Some(normals) if has_normals => data.normals.extend(normals.map(|n| {
+                    (normal_matrix * Vector3::new(n[0] as f64, n[1] as f64, n[2] as f64)).normalize()
+                })),
+                _ => has_normals = false,
+            }
+            match reader.read_tex_coords(0) {
+                Some(uvs) => data.uvs.extend(uvs.into_f32().map(|uv| (uv[0] as f64, 1.0 - uv[1] as f64))),
+                None => data.uvs.extend(std::iter::repeat_n((0.0, 0.0), vertex_count)),
+            }
+
+            materials.push(self.material(&primitive.material()));
+            let material_index = materials.len() - 1;
+            for [i0, i1, i2] in triangles {
+                data.indices.push([base + i0, base + i1, base + i2]);
+                data.material_indices.push(material_index);
+            }
+        }
+
+        if data.indices.is_empty() {
+            return Ok(None);
+        }
+        if !has_normals || data.normals.len() != data.positions.len() {
+            data.normals.clear();
+        }
+        let shading = if data.normals.is_empty() { Shading::Flat } else { Shading::Smooth };
+        Ok(Some(TriangleMesh::new(data, materials, shading)))
+    }
+
+    fn material(&mut self, material: &gltf::Material) -> Arc<dyn Material> {
+        self.materials
+            .entry(material.index())
+            .or_insert_with(|| {
+                let pbr = material.pbr_metallic_roughness();
+                let [r, g, b, _alpha] = pbr.base_color_factor();
+                let base_color = Color::new(r as f64, g as f64, b as f64);
+                let [er, eg, eb] = material.emissive_factor();
+                let emission = material.emissive_strength().unwrap_or(1.0) as f64
+                    * Color::new(er as f64, eg as f64, eb as f64);
+                let transmission = material.transmission().map_or(0.0, |t| t.transmission_factor());
+                if emission.max() > 0.0 {
+                    Arc::new(DiffuseLight::new(emission))
+                } else if transmission > 0.5 {
+                    let index_of_refraction = material.ior().unwrap_or(1.5) as f64;
+                    Arc::new(Dielectric { index_of_refraction })
+                } else if pbr.metallic_factor() >= 0.5 {
+                    Arc::new(Metal::new(base_color, pbr.roughness_factor() as f64))
+                } else {
+                    Arc::new(Lambertian::new(base_color))
+                }
+            })
+            .clone()
+    }
+
+    fn camera(&self, yfov: f64, transform: &Matrix4<f64>) -> Camera {
+        // glTF cameras look down their local -Z axis with +Y up.
+        let origin = transform * Vector4::new(0.0, 0.0, 0.0, 1.0);
+        let forward = transform * Vector4::new(0.0, 0.0, -1.0, 0.0);
+        let up = transform * Vector4::new(0.0, 1.0, 0.0, 0.0);
+        let look_from = Point::new(origin.x, origin.y, origin.z);
+        let look_at = look_from + Vector3::new(forward.x, forward.y, forward.z).normalize();
+        Camera::new(
+            look_from,
+            look_at,
+            Vector3::new(up.x, up.y, up.z),
+            yfov.to_degrees(),
+            self.aspect_ratio,
+            0.0,
+            1.0,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{hittable::Hittable, ray::Ray, test_util::TempFile};
+
+    /// Loads a one-triangle glTF whose primitive uses `mode` and `indices`,
+    /// moved to z = -2 by its node.
+    fn load_triangle(name: &str, mode: u32, indices: [u16; 3]) -> Result<GltfScene, LoadError> {
+        let mut bin = Vec::new();
+        for value in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+            bin.extend(value.to_le_bytes());
+        }
+        for index in indices {
+            bin.extend(index.to_le_bytes());
+        }
+        bin.extend([0, 0]);
+        let bin = TempFile::new(&format!("{name}.bin"), bin);
+        let json = format!(
+            r#"{{
+                "asset": {{ "version": "2.0" }},
+                "scene": 0,
+                "scenes": [{{ "nodes": [0] }}],
+                "nodes": [{{ "mesh": 0, "translation": [0, 0, -2] }}],
+                "meshes": [{{ "primitives": [{{ "attributes": {{ "POSITION": 0 }}, "indices": 1, "mode": {mode} }}] }}],
+                "buffers": [{{ "uri": "{}", "byteLength": 44 }}],
+                "bufferViews": [
+                    {{ "buffer": 0, "byteOffset": 0, "byteLength": 36 }},
+                    {{ "buffer": 0, "byteOffset": 36, "byteLength": 6 }}
+                ],
+                "accessors": [
+                    {{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+                       "min": [0, 0, 0], "max": [1, 1, 0] }},
+                    {{ "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }}
+                ]
+            }}"#,
+            bin.file_name()
+        );
+        let gltf = TempFile::new(&format!("{name}.gltf"), json);
+        load_gltf(gltf.path(), 1.0)
+    }
+
+    #[test]
+    fn loads_a_transformed_triangle() {
+        let scene = load_triangle("triangle", 4, [0, 1, 2]).unwrap();
+        assert!(scene.camera.is_none());
+        let ray = Ray::new(Point::new(0.2, 0.2, 0.0), Vector3::new(0.0, 0.0, -1.0), 0.0);
+        let hit = scene.world.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert!((hit.t - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn out_of_range_index_is_a_format_error() {
+        let error = load_triangle("bad_index", 4, [0, 1, 5]).err().unwrap();
+        assert!(matches!(error, LoadError::Format { .. }), "{error}");
+        assert!(error.to_string().contains("index 5"), "{error}");
+    }
+
+    #[test]
+    fn line_primitives_are_a_format_error() {
+        let error = load_triangle("lines", 1, [0, 1, 2]).err().unwrap();
+        assert!(error.to_string().contains("unsupported mode"), "{error}");
+    }
+}
@@ -1,8 +1,9 @@
 use std::sync::Arc;
 
-use super::{material::Material, point::Point, ray::Ray, aabb::AABB};
+use super::{material::Material, point::Point, ray::Ray, aabb::AABB, texture::Footprint};
 use nalgebra::Vector3;
 
+#[derive(Clone)]
 pub struct HitRecord {
     pub t: f64,
     pub p: Point,
@@ -11,6 +12,9 @@ pub struct HitRecord {
     pub normal: Vector3<f64>,
     pub front_face: bool,
     pub material: Arc<dyn Material>,
+    // Partial derivatives of the surface position with respect to u and v.
+    pub dpdu: Vector3<f64>,
+    pub dpdv: Vector3<f64>,
 }
 
 
@@ -23,10 +27,92 @@ impl HitRecord {
             -self.normal
         };
     }
+
+    /// Texture space footprint of the cone around `ray` where it hits this
+    /// surface, or `None` without a cone or usable surface derivatives.
+    pub fn footprint(&self, ray: &Ray) -> Option<Footprint> {
+        let cone = ray.cone()?;
+        let width = cone.at(self.t * ray.direction().magnitude()).width.abs();
+        let direction = ray.direction().normalize();
+        let normal = self.normal;
+
+        // The cone's circular section stretches into an ellipse along the
+        // projection of the ray onto the surface.
+        let cos_theta = direction.dot(&normal).abs().max(0.01);
+        let along = direction - direction.dot(&normal) * normal;
+        let along = if along.magnitude_squared() > 1e-12 { along.normalize() } else { self.dpdu.try_normalize(1e-12)? };
+        let across = normal.cross(&along);
+
+        // Solve for the (u, v) offsets reaching each world space axis.
+        let (uu, uv, vv) = (self.dpdu.dot(&self.dpdu), self.dpdu.dot(&self.dpdv), self.dpdv.dot(&self.dpdv));
+        let determinant = uu * vv - uv * uv;
+        if determinant <= 1e-12 * uu * vv {
+            return None;
+        }
+        let to_uv = |axis: Vector3<f64>| {
+            let (a, b) = (self.dpdu.dot(&axis), self.dpdv.dot(&axis));
+            ((vv * a - uv * b) / determinant, (uu * b - uv * a) / determinant)
+        };
+        Some(Footprint { major: to_uv(width / cos_theta * along), minor: to_uv(width * across) })
+    }
 }
 pub trait Hittable : Sync + Send{
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
     fn bounding_box(&self, time0:f64, time1:f64) ->  Option<AABB>;
+
+    /// Solid angle density of `random` picking `direction` from `origin`,
+    /// zero for shapes that cannot be sampled as lights.
+    fn pdf_value(&self, _origin: &Point, _direction: &Vector3<f64>) -> f64 {
+        0.0
+    }
+
+    /// Direction from `origin` towards a random point on the shape.
+    ///
+    /// Shapes that cannot be sampled return an arbitrary direction, so the
+    /// result is only usable when `pdf_value` is positive for it. Callers
+    /// must check that, and a `HittableList` of lights should only hold
+    /// shapes that implement both methods: it picks one of them at random,
+    /// and another one may still give the arbitrary direction a density.
+    fn random(&self, _origin: &Point) -> Vector3<f64> {
+        Vector3::x()
+    }
+
+    /// Fraction of the light travelling along the ray between `t_min` and
+    /// `t_max` that gets through, used for shadow rays. Surfaces block it
+    /// entirely, participating media let part of it pass.
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        if self.hit(ray, t_min, t_max).is_some() {
+            0.0
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Solid angle density at the origin of a ray with `direction` of picking
+/// points uniformly over a surface of `area`, when the ray meets it at `t`
+/// where its normal is `normal`.
+pub(crate) fn area_pdf(direction: &Vector3<f64>, t: f64, normal: &Vector3<f64>, area: f64) -> f64 {
+    let length = direction.magnitude();
+    let distance_squared = t * t * length * length;
+    let cosine = direction.dot(normal).abs() / (length * normal.magnitude());
+    if cosine <= 0.0 || area <= 0.0 {
+        return 0.0;
+    }
+    distance_squared / (cosine * area)
+}
+
+/// Lets many objects share one prototype, see `Instance`.
+impl<H: Hittable + ?Sized> Hittable for Arc<H> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> { self.as_ref().hit(ray, t_min, t_max) }
+
+    fn bounding_box(&self, t0: f64, t1: f64) -> Option<AABB> { self.as_ref().bounding_box(t0, t1) }
+
+    fn pdf_value(&self, origin: &Point, direction: &Vector3<f64>) -> f64 { self.as_ref().pdf_value(origin, direction) }
+
+    fn random(&self, origin: &Point) -> Vector3<f64> { self.as_ref().random(origin) }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 { self.as_ref().transmittance(ray, t_min, t_max) }
 }
 
 pub struct FlipNormals<H: Hittable> {
@@ -46,4 +132,29 @@ impl<H: Hittable> Hittable for FlipNormals<H> {
     }
 
     fn bounding_box(&self, t0: f64, t1: f64) -> Option<AABB> { self.hitable.bounding_box(t0, t1) }
-}
\ No newline at end of file
+
+    fn pdf_value(&self, origin: &Point, direction: &Vector3<f64>) -> f64 { self.hitable.pdf_value(origin, direction) }
+
+    fn random(&self, origin: &Point) -> Vector3<f64> { self.hitable.random(origin) }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 { self.hitable.transmittance(ray, t_min, t_max) }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn area_pdf_converts_area_density_to_solid_angle() {
+        // A 4 unit² surface facing the origin 2 units away: 2² / (1 * 4).
+        let normal = Vector3::new(0.0, 0.0, -1.0);
+        assert_eq!(area_pdf(&Vector3::new(0.0, 0.0, 1.0), 2.0, &normal, 4.0), 1.0);
+        // The density does not depend on the direction's length.
+        assert_eq!(area_pdf(&Vector3::new(0.0, 0.0, 2.0), 1.0, &normal, 4.0), 1.0);
+        // Tilting the surface by 60° halves the cosine.
+        let tilted = Vector3::new(3.0f64.sqrt() / 2.0, 0.0, -0.5);
+        assert!((area_pdf(&Vector3::new(0.0, 0.0, 1.0), 2.0, &tilted, 4.0) - 2.0).abs() < 1e-12);
+        // Grazing rays and empty surfaces have no density.
+        assert_eq!(area_pdf(&Vector3::new(1.0, 0.0, 0.0), 2.0, &normal, 4.0), 0.0);
+        assert_eq!(area_pdf(&Vector3::new(0.0, 0.0, 1.0), 2.0, &normal, 0.0), 0.0);
+    }
+}
@@ -3,7 +3,9 @@ use nalgebra::Vector3;
 use super::{
     aabb::{surrounding_box, AABB},
     hittable::{HitRecord, Hittable},
+    point::Point,
     ray::Ray,
+    util::random_double,
 };
 
 #[derive(Default)]
@@ -19,6 +21,14 @@ impl HittableList {
     pub fn new(list: Vec<Box<dyn Hittable>>) -> Self {
         HittableList { list }
     }
+
+    pub fn into_objects(self) -> Vec<Box<dyn Hittable>> {
+        self.list
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
 }
 
 impl Hittable for HittableList {
@@ -59,4 +69,32 @@ impl Hittable for HittableList {
             Some(result)
         }
     }
+
+    /// Samples each object with equal probability.
+    fn pdf_value(&self, origin: &Point, direction: &Vector3<f64>) -> f64 {
+        if self.list.is_empty() {
+            return 0.0;
+        }
+        let sum: f64 = self.list.iter().map(|object| object.pdf_value(origin, direction)).sum();
+        sum / self.list.len() as f64
+    }
+
+    fn random(&self, origin: &Point) -> Vector3<f64> {
+        match self.list.len() {
+            // Arbitrary, `pdf_value` is zero for an empty list.
+            0 => Vector3::x(),
+            n => self.list[((random_double() * n as f64) as usize).min(n - 1)].random(origin),
+        }
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        let mut transmittance = 1.0;
+        for object in self.list.iter() {
+            transmittance *= object.transmittance(ray, t_min, t_max);
+            if transmittance <= 0.0 {
+                break;
+            }
+        }
+        transmittance
+    }
 }
@@ -0,0 +1,57 @@
+use std::sync::Arc;
+
+use nalgebra::{Matrix4, Vector3};
+
+use super::{
+    aabb::AABB,
+    hittable::{HitRecord, Hittable},
+    material::Material,
+    point::Point,
+    ray::Ray,
+    transform::Transform,
+};
+
+/// One placement of a shared prototype, such as a mesh with its own BVH,
+/// so that many copies only cost a transform each. The instance can shade
+/// the prototype with its own material instead of the prototype's.
+pub struct Instance {
+    transform: Transform<Arc<dyn Hittable>>,
+    material: Option<Arc<dyn Material>>,
+}
+
+impl Instance {
+    pub fn new(prototype: Arc<dyn Hittable>, matrix: Matrix4<f64>) -> Self {
+        Instance { transform: Transform::new(prototype, matrix), material: None }
+    }
+
+    pub fn with_material(mut self, material: Arc<dyn Material>) -> Self {
+        self.material = Some(material);
+        self
+    }
+}
+
+impl Hittable for Instance {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let mut hit = self.transform.hit(ray, t_min, t_max)?;
+        if let Some(material) = &self.material {
+            hit.material = material.clone();
+        }
+        Some(hit)
+    }
+
+    fn bounding_box(&self, t0: f64, t1: f64) -> Option<AABB> {
+        self.transform.bounding_box(t0, t1)
+    }
+
+    fn pdf_value(&self, origin: &Point, direction: &Vector3<f64>) -> f64 {
+        self.transform.pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: &Point) -> Vector3<f64> {
+        self.transform.random(origin)
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        self.transform.transmittance(ray, t_min, t_max)
+    }
+}
@@ -0,0 +1,242 @@
+use super::{
+    color::Color,
+    hittable::{HitRecord, Hittable},
+    material::ScatterRecord,
+    pdf::Pdf,
+    ray::Ray,
+    roulette::RussianRoulette,
+    scene::Scene,
+    util::random_double,
+};
+
+/// Estimates the radiance arriving along a camera ray.
+pub trait Integrator: Send + Sync {
+    fn radiance(&self, ray: &Ray, scene: &Scene) -> Color;
+}
+
+/// Rule for weighting samples of one strategy against another that could
+/// have produced the same direction in multiple importance sampling.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MisHeuristic {
+    Balance,
+    Power,
+}
+
+impl MisHeuristic {
+    /// Weight of a sample drawn with density `pdf` when the other strategy
+    /// would have drawn it with density `other`.
+    pub fn weight(self, pdf: f64, other: f64) -> f64 {
+        let (pdf, other) = match self {
+            MisHeuristic::Balance => (pdf, other),
+            MisHeuristic::Power => (pdf * pdf, other * other),
+        };
+        if pdf + other > 0.0 {
+            pdf / (pdf + other)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Unidirectional path tracer following one scattered ray per bounce, for
+/// at most `max_depth` bounces and ended early by Russian roulette.
+///
+/// At diffuse bounces the scene's lights and background are also sampled
+/// directly, combined with the scattered rays through `heuristic`, and
+/// every analytic light is tested with a shadow ray.
+pub struct PathTracer {
+    pub max_depth: i32,
+    pub roulette: RussianRoulette,
+    pub heuristic: MisHeuristic,
+}
+
+impl PathTracer {
+    pub fn new(max_depth: i32) -> Self {
+        PathTracer { max_depth, roulette: RussianRoulette::default(), heuristic: MisHeuristic::Power }
+    }
+
+    /// Light reaching `hit` straight from the scene's lights and background,
+    /// scattered towards `ray_in`. Scattered rays never find analytic lights,
+    /// so specular surfaces do not reflect them. Shadow rays are weighted by
+    /// the transmittance of the world up to the light, so participating
+    /// media dim it rather than block it.
+    fn sample_lights(&self, scene: &Scene, ray_in: &Ray, hit: &HitRecord, attenuation: &Color, pdf: &dyn Pdf) -> Color {
+        let mut radiance = Color::zeros();
+        let mut add = |shadow: &Ray, incoming: Color, light_pdf: f64| {
+            let scattering_pdf = hit.material.scattering_pdf(ray_in, hit, shadow);
+            if scattering_pdf > 0.0 {
+                let weight = self.heuristic.weight(light_pdf, pdf.value(&shadow.direction()));
+                radiance += weight * scattering_pdf / light_pdf * attenuation.component_mul(&incoming);
+            }
+        };
+
+        if !scene.lights.is_empty() {
+            let shadow = Ray::new(hit.p, scene.lights.random(&hit.p), ray_in.time());
+            let light_pdf = scene.lights.pdf_value(&hit.p, &shadow.direction());
+            if light_pdf > 0.0 {
+                if let Some(light) = scene.lights.hit(&shadow, 0.001, f64::MAX) {
+                    let transmittance = scene.world.transmittance(&shadow, 0.001, light.t * (1.0 - 1e-6));
+                    if transmittance > 0.0 {
+                        add(&shadow, transmittance * light.material.emitted(light.u, light.v, &light.p), light_pdf);
+                    }
+                }
+            }
+        }
+        if let Some((direction, background_pdf)) = scene.background.sample_direction() {
+            let shadow = Ray::new(hit.p, direction, ray_in.time());
+            if background_pdf > 0.0 {
+                let transmittance = scene.world.transmittance(&shadow, 0.001, f64::MAX);
+                if transmittance > 0.0 {
+                    add(&shadow, transmittance * scene.background.color(&direction), background_pdf);
+                }
+            }
+        }
+
+        // Lights without area can only be found this way, so no weighting.
+        for light in &scene.analytic_lights {
+            let sample = match light.sample(&hit.p) {
+                Some(sample) => sample,
+                None => continue,
+            };
+            let shadow = Ray::new(hit.p, sample.direction, ray_in.time());
+            let transmittance = scene.world.transmittance(&shadow, 0.001, sample.distance * (1.0 - 1e-6));
+            if transmittance > 0.0 {
+                let scattering_pdf = hit.material.scattering_pdf(ray_in, hit, &shadow);
+                radiance += transmittance * scattering_pdf * attenuation.component_mul(&sample.radiance);
+            }
+        }
+        radiance
+    }
+}
+
+impl Integrator for PathTracer {
+    fn radiance(&self, ray: &Ray, scene: &Scene) -> Color {
+        let mut radiance = Color::zeros();
+        // Weight of the current ray's radiance in the final sample.
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        let mut ray = ray.clone();
+        // Density the last diffuse bounce sampled `ray` with, used to weight
+        // light it finds against direct light sampling. None for camera rays
+        // and specular bounces, which direct sampling cannot reproduce.
+        let mut scatter_pdf: Option<f64> = None;
+
+        for bounce in 0..self.max_depth {
+            let hit = match scene.world.hit(&ray, 0.001, f64::MAX) {
+                Some(hit) => hit,
+                None => {
+                    let direction = ray.direction();
+                    let weight = scatter_pdf.map_or(1.0, |pdf| self.heuristic.weight(pdf, scene.background.pdf(&direction)));
+                    radiance += weight * throughput.component_mul(&scene.background.color(&direction));
+                    break;
+                }
+            };
+            let emitted = hit.material.emitted(hit.u, hit.v, &hit.p);
+            if emitted != Color::zeros() {
+                let weight = scatter_pdf.map_or(1.0, |pdf| {
+                    self.heuristic.weight(pdf, scene.lights.pdf_value(&ray.origin(), &ray.direction()))
+                });
+                radiance += weight * throughput.component_mul(&emitted);
+            }
+
+            let (scattered, weight) = match hit.material.scatter(&ray, &hit) {
+                Some(ScatterRecord::Specular { ray, attenuation }) => {
+                    scatter_pdf = None;
+                    (ray, attenuation)
+                }
+                Some(ScatterRecord::Diffuse { attenuation, pdf }) => {
+                    radiance += throughput.component_mul(&self.sample_lights(scene, &ray, &hit, &attenuation, pdf.as_ref()));
+                    let scattered = Ray::new(hit.p, pdf.generate(), ray.time());
+                    let pdf_value = pdf.value(&scattered.direction());
+                    let scattering_pdf = hit.material.scattering_pdf(&ray, &hit, &scattered);
+                    if pdf_value <= 0.0 || scattering_pdf <= 0.0 {
+                        break;
+                    }
+                    scatter_pdf = Some(pdf_value);
+                    (scattered, attenuation * scattering_pdf / pdf_value)
+                }
+                None => break,
+            };
+
+            throughput.component_mul_assign(&weight);
+            let survival = self.roulette.survival_probability(bounce + 1, &throughput);
+            if survival < 1.0 && random_double() >= survival {
+                break;
+            }
+            throughput /= survival;
+            ray = scattered;
+        }
+        radiance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{f64::consts::PI, sync::Arc};
+
+    use nalgebra::Vector3;
+
+    use super::*;
+    use crate::models::{
+        background::SolidColor,
+        hittable_list::HittableList,
+        material::{DiffuseLight, Lambertian, Material},
+        point::Point,
+        rect::{AARect, Plane},
+    };
+
+    const ALBEDO: f64 = 0.5;
+
+    /// A white floor at y = 0 under a unit square light at y = 1.
+    fn floor_and_light(light_material: Arc<dyn Material>, sample_lights: bool) -> Scene {
+        let floor = Arc::new(Lambertian::new(Color::new(ALBEDO, ALBEDO, ALBEDO)));
+        let light = Arc::new(DiffuseLight::new(Color::new(1.0, 1.0, 1.0)));
+        let mut world = HittableList::default();
+        world.push(AARect::new(Plane::ZX, -10.0, 10.0, -10.0, 10.0, 0.0, floor));
+        world.push(AARect::new(Plane::ZX, -0.5, 0.5, -0.5, 0.5, 1.0, light));
+        let mut lights = HittableList::default();
+        if sample_lights {
+            lights.push(AARect::new(Plane::ZX, -0.5, 0.5, -0.5, 0.5, 1.0, light_material));
+        }
+        Scene {
+            world: Arc::new(world),
+            lights,
+            analytic_lights: Vec::new(),
+            background: Arc::new(SolidColor { color: Color::zeros() }),
+        }
+    }
+
+    /// Radiance leaving the floor below the light's center, which the
+    /// Lambertian floor scatters from the light's form factor.
+    fn expected_radiance() -> f64 {
+        // Form factor from a point to a parallel rectangle with one corner
+        // above it, for each quarter of the light.
+        let (x, y) = (0.5f64, 0.5f64);
+        let (sx, sy) = ((1.0 + x * x).sqrt(), (1.0 + y * y).sqrt());
+        let quarter = (x / sx * (y / sx).atan() + y / sy * (x / sy).atan()) / (2.0 * PI);
+        ALBEDO * 4.0 * quarter
+    }
+
+    fn mean_radiance(integrator: &dyn Integrator, scene: &Scene, samples: usize) -> f64 {
+        let ray = Ray::new(Point::new(0.0, 0.5, -0.5), Vector3::new(0.0, -1.0, 1.0), 0.0);
+        (0..samples).map(|_| integrator.radiance(&ray, scene).x).sum::<f64>() / samples as f64
+    }
+
+    #[test]
+    fn direct_light_through_lights_copy_matches_emitter() {
+        let light = Arc::new(DiffuseLight::new(Color::new(1.0, 1.0, 1.0)));
+        let radiance = mean_radiance(&PathTracer::new(2), &floor_and_light(light, true), 20_000);
+        let expected = expected_radiance();
+        assert!((radiance - expected).abs() < 0.02 * expected, "{radiance} vs {expected}");
+    }
+
+    #[test]
+    fn light_sampling_agrees_with_bsdf_sampling() {
+        let light = Arc::new(DiffuseLight::new(Color::new(1.0, 1.0, 1.0)));
+        let integrator = PathTracer::new(2);
+        let with_lights = mean_radiance(&integrator, &floor_and_light(light.clone(), true), 100_000);
+        let bsdf_only = mean_radiance(&integrator, &floor_and_light(light, false), 100_000);
+        let expected = expected_radiance();
+        assert!((with_lights - bsdf_only).abs() < 0.03 * expected, "{with_lights} vs {bsdf_only}");
+        assert!((bsdf_only - expected).abs() < 0.03 * expected, "{bsdf_only} vs {expected}");
+    }
+}
@@ -0,0 +1,117 @@
+use nalgebra::Vector3;
+
+use super::{color::Color, point::Point};
+
+/// Light reaching a point from a light that is not part of the geometry.
+pub struct LightSample {
+    /// Unit direction from the lit point towards the light.
+    pub direction: Vector3<f64>,
+    /// Distance to the light, infinite for directional lights.
+    pub distance: f64,
+    /// Incident radiance integrated over the light, ignoring occlusion.
+    pub radiance: Color,
+}
+
+/// Point-like or directional light source. These have no area so they can
+/// only be reached by shadow rays, never by scattered rays.
+pub trait Light: Send + Sync {
+    fn sample(&self, point: &Point) -> Option<LightSample>;
+}
+
+/// Light emitted equally in all directions from `position`.
+pub struct PointLight {
+    pub position: Point,
+    pub color: Color,
+    pub intensity: f64,
+}
+
+impl Light for PointLight {
+    fn sample(&self, point: &Point) -> Option<LightSample> {
+        let offset = self.position - point;
+        let distance = offset.magnitude();
+        if distance <= 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            direction: offset / distance,
+            distance,
+            radiance: self.intensity / (distance * distance) * self.color,
+        })
+    }
+}
+
+/// Point light restricted to a cone around `direction`, at full intensity
+/// within `inner_angle` and fading out smoothly up to `outer_angle`.
+pub struct SpotLight {
+    position: Point,
+    direction: Vector3<f64>,
+    color: Color,
+    intensity: f64,
+    cos_inner: f64,
+    cos_outer: f64,
+}
+
+impl SpotLight {
+    /// Angles are half angles of the cones in degrees.
+    pub fn new(position: Point, direction: Vector3<f64>, color: Color, intensity: f64, inner_angle: f64, outer_angle: f64) -> Self {
+        let outer_angle = outer_angle.max(inner_angle);
+        SpotLight {
+            position,
+            direction: direction.normalize(),
+            color,
+            intensity,
+            cos_inner: inner_angle.to_radians().cos(),
+            cos_outer: outer_angle.to_radians().cos(),
+        }
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(&self, point: &Point) -> Option<LightSample> {
+        let offset = self.position - point;
+        let distance = offset.magnitude();
+        if distance <= 0.0 {
+            return None;
+        }
+        let direction = offset / distance;
+        let cos_angle = -direction.dot(&self.direction);
+        if cos_angle <= self.cos_outer {
+            return None;
+        }
+        let falloff = if cos_angle >= self.cos_inner {
+            1.0
+        } else {
+            let t = (cos_angle - self.cos_outer) / (self.cos_inner - self.cos_outer);
+            t * t * (3.0 - 2.0 * t)
+        };
+        Some(LightSample {
+            direction,
+            distance,
+            radiance: falloff * self.intensity / (distance * distance) * self.color,
+        })
+    }
+}
+
+/// Distant light such as the sun shining along `direction` everywhere,
+/// with `intensity` as the irradiance on a surface facing it.
+pub struct DirectionalLight {
+    direction: Vector3<f64>,
+    color: Color,
+    intensity: f64,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Vector3<f64>, color: Color, intensity: f64) -> Self {
+        DirectionalLight { direction: direction.normalize(), color, intensity }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _point: &Point) -> Option<LightSample> {
+        Some(LightSample {
+            direction: -self.direction,
+            distance: f64::INFINITY,
+            radiance: self.intensity * self.color,
+        })
+    }
+}
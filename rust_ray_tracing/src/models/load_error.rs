@@ -0,0 +1,50 @@
+use std::{error::Error, fmt, io};
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    Parse { file: String, line: usize, message: String },
+    Format { file: String, message: String },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(error) => write!(f, "{error}"),
+            LoadError::Parse { file, line, message } => write!(f, "{file}:{line}: {message}"),
+            LoadError::Format { file, message } => write!(f, "{file}: {message}"),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(error: io::Error) -> Self {
+        LoadError::Io(error)
+    }
+}
+
+/// Position in a text file, used by the loaders to build `LoadError::Parse`.
+pub(crate) struct LineContext<'a> {
+    pub file: &'a str,
+    pub line: usize,
+}
+
+impl LineContext<'_> {
+    pub fn error(&self, message: impl Into<String>) -> LoadError {
+        LoadError::Parse { file: self.file.to_string(), line: self.line, message: message.into() }
+    }
+
+    pub fn parse<T: std::str::FromStr>(&self, token: Option<&str>) -> Result<T, LoadError> {
+        let token = token.ok_or_else(|| self.error("missing value"))?;
+        token.parse().map_err(|_| self.error(format!("invalid value '{token}'")))
+    }
+}
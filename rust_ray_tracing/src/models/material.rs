@@ -1,52 +1,101 @@
+use std::{f64::consts::PI, sync::Arc};
+
 use nalgebra::Vector3;
 
 use super::{
     color::Color,
     hittable::HitRecord,
+    pdf::{henyey_greenstein, CosinePdf, HenyeyGreensteinPdf, Pdf, SpherePdf},
+    point::Point,
     ray::Ray,
+    texture::{ConstantTexture, Texture},
     util::{random_double, random_vector_in_unit_sphere},
 };
 
+/// How light leaves a surface after a scattering event.
+pub enum ScatterRecord {
+    /// A delta lobe: the only direction light can leave in is `ray`.
+    Specular { ray: Ray, attenuation: Color },
+    /// Light leaves in any direction, sampled from `pdf`. The contribution of
+    /// a direction is `attenuation * scattering_pdf / pdf.value`.
+    Diffuse { attenuation: Color, pdf: Box<dyn Pdf> },
+}
+
 pub trait Material: Send + Sync {
-    fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<(Ray, Color)>;
+    fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord>;
+
+    /// Solid angle density of light scattering into `scattered`, for
+    /// materials returning `ScatterRecord::Diffuse`.
+    fn scattering_pdf(&self, _ray_in: &Ray, _hit_record: &HitRecord, _scattered: &Ray) -> f64 {
+        0.0
+    }
+
+    fn emitted(&self, _u: f64, _v: f64, _p: &Point) -> Color {
+        Color::zeros()
+    }
 }
 
 pub struct Lambertian {
-    pub albedo: Color,
+    pub albedo: Arc<dyn Texture>,
+}
+
+impl Lambertian {
+    pub fn new(albedo: Color) -> Self {
+        Lambertian { albedo: Arc::new(ConstantTexture { color: albedo }) }
+    }
+
+    pub fn with_texture(albedo: Arc<dyn Texture>) -> Self {
+        Lambertian { albedo }
+    }
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, _ray_in: &Ray, hit_record: &HitRecord) -> Option<(Ray, Color)> {
-        let mut scatter_direction = hit_record.normal + random_vector_in_unit_sphere();
-        let is_near_0 = scatter_direction.x.abs() < 1e-8
-            && scatter_direction.y.abs() < 1e-8
-            && scatter_direction.z.abs() < 1e-8;
-        if is_near_0 {
-            scatter_direction = hit_record.normal;
-        }
-        let scattered = Ray::new(hit_record.p, scatter_direction);
-        return Some((scattered, self.albedo));
+    fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord> {
+        Some(ScatterRecord::Diffuse {
+            attenuation: texture_value(self.albedo.as_ref(), ray_in, hit_record),
+            pdf: Box::new(CosinePdf::new(&hit_record.normal)),
+        })
+    }
+
+    fn scattering_pdf(&self, _ray_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> f64 {
+        let cosine = hit_record.normal.dot(&scattered.direction().normalize());
+        (cosine / PI).max(0.0)
     }
 }
 
 pub struct Metal {
-    pub albedo: Color,
+    pub albedo: Arc<dyn Texture>,
     pub fuzz: f64,
 }
 
+impl Metal {
+    pub fn new(albedo: Color, fuzz: f64) -> Self {
+        Metal { albedo: Arc::new(ConstantTexture { color: albedo }), fuzz }
+    }
+
+    pub fn with_texture(albedo: Arc<dyn Texture>, fuzz: f64) -> Self {
+        Metal { albedo, fuzz }
+    }
+}
+
 impl Material for Metal {
-    fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<(Ray, Color)> {
+    fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord> {
         let fuzz = if self.fuzz < 1.0 { self.fuzz } else { 1.0 };
 
         let reflected = reflect(ray_in.direction().normalize(), hit_record.normal);
         let scattered = Ray::new(
             hit_record.p,
             reflected + fuzz * random_vector_in_unit_sphere(),
-        );
+            ray_in.time(),
+        )
+        .with_cone_from(ray_in, hit_record.t, fuzz);
         if scattered.direction().dot(&hit_record.normal) > 0.0 {
-            return Some((scattered, self.albedo));
+            Some(ScatterRecord::Specular {
+                ray: scattered,
+                attenuation: texture_value(self.albedo.as_ref(), ray_in, hit_record),
+            })
         } else {
-            return None;
+            None
         }
     }
 }
@@ -56,7 +105,7 @@ pub struct Dielectric {
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<(Ray, Color)> {
+    fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord> {
         let attenuation = Vector3::new(1.0, 1.0, 1.0);
         let refraction_ratio = if hit_record.front_face {
             1.0 / self.index_of_refraction
@@ -77,8 +126,117 @@ impl Material for Dielectric {
             } else {
                 refract(&unit_direction, &hit_record.normal, refraction_ratio)
             };
-        let scattered = Ray::new(hit_record.p, direction);
-        Some((scattered, attenuation))
+        let scattered = Ray::new(hit_record.p, direction, ray_in.time()).with_cone_from(ray_in, hit_record.t, 0.0);
+        Some(ScatterRecord::Specular { ray: scattered, attenuation })
+    }
+}
+
+pub struct DiffuseLight {
+    pub emit: Arc<dyn Texture>,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> Self {
+        DiffuseLight { emit: Arc::new(ConstantTexture { color: emit }) }
+    }
+
+    pub fn with_texture(emit: Arc<dyn Texture>) -> Self {
+        DiffuseLight { emit }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(&self, _ray_in: &Ray, _hit_record: &HitRecord) -> Option<ScatterRecord> {
+        None
+    }
+
+    fn emitted(&self, u: f64, v: f64, p: &Point) -> Color {
+        self.emit.value(u, v, p)
+    }
+}
+
+/// Phase function scattering light equally in every direction, used for the
+/// inside of participating media.
+pub struct Isotropic {
+    pub albedo: Arc<dyn Texture>,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Color) -> Self {
+        Isotropic { albedo: Arc::new(ConstantTexture { color: albedo }) }
+    }
+
+    pub fn with_texture(albedo: Arc<dyn Texture>) -> Self {
+        Isotropic { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(&self, _ray_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord> {
+        Some(ScatterRecord::Diffuse {
+            attenuation: self.albedo.value(hit_record.u, hit_record.v, &hit_record.p),
+            pdf: Box::new(SpherePdf),
+        })
+    }
+
+    fn scattering_pdf(&self, _ray_in: &Ray, _hit_record: &HitRecord, _scattered: &Ray) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+}
+
+/// Anisotropic phase function for participating media, see
+/// `HenyeyGreensteinPdf` for `g`. With `emission`, collisions also emit the
+/// part of it that is absorbed rather than scattered, as fire or hot smoke.
+pub struct HenyeyGreenstein {
+    pub albedo: Arc<dyn Texture>,
+    pub g: f64,
+    pub emission: Option<Arc<dyn Texture>>,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(albedo: Color, g: f64) -> Self {
+        HenyeyGreenstein { albedo: Arc::new(ConstantTexture { color: albedo }), g, emission: None }
+    }
+
+    pub fn with_texture(albedo: Arc<dyn Texture>, g: f64) -> Self {
+        HenyeyGreenstein { albedo, g, emission: None }
+    }
+
+    pub fn with_emission(mut self, emission: Arc<dyn Texture>) -> Self {
+        self.emission = Some(emission);
+        self
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord> {
+        Some(ScatterRecord::Diffuse {
+            attenuation: self.albedo.value(hit_record.u, hit_record.v, &hit_record.p),
+            pdf: Box::new(HenyeyGreensteinPdf::new(&ray_in.direction(), self.g)),
+        })
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, _hit_record: &HitRecord, scattered: &Ray) -> f64 {
+        let cos_theta = ray_in.direction().normalize().dot(&scattered.direction().normalize());
+        henyey_greenstein(cos_theta, self.g)
+    }
+
+    fn emitted(&self, u: f64, v: f64, p: &Point) -> Color {
+        match &self.emission {
+            Some(emission) => {
+                let absorbed = Color::new(1.0, 1.0, 1.0) - self.albedo.value(u, v, p);
+                absorbed.component_mul(&emission.value(u, v, p))
+            }
+            None => Color::zeros(),
+        }
+    }
+}
+
+/// Looks up `texture` at a hit, filtered over the footprint of the ray's cone.
+fn texture_value(texture: &dyn Texture, ray_in: &Ray, hit_record: &HitRecord) -> Color {
+    match hit_record.footprint(ray_in) {
+        Some(footprint) => texture.filtered_value(hit_record.u, hit_record.v, &hit_record.p, &footprint),
+        None => texture.value(hit_record.u, hit_record.v, &hit_record.p),
     }
 }
 
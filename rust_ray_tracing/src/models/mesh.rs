@@ -0,0 +1,217 @@
+use std::sync::Arc;
+
+use nalgebra::Vector3;
+
+use super::{
+    aabb::AABB,
+    hittable::{area_pdf, HitRecord, Hittable},
+    material::Material,
+    point::Point,
+    ray::Ray,
+    sah_bvh::{BvhStats, LinearBvh},
+    triangle::{hit_record, intersect, sample_triangle, triangle_bounds},
+    util::random_double,
+};
+
+/// Vertex and index buffers of a triangle mesh. The optional buffers are
+/// either empty or hold one entry per position (`normals`, `uvs`) or per
+/// face (`material_indices`, indexing the mesh's materials).
+#[derive(Debug, Default, PartialEq)]
+pub struct MeshData {
+    pub positions: Vec<Point>,
+    pub normals: Vec<Vector3<f64>>,
+    pub uvs: Vec<(f64, f64)>,
+    pub indices: Vec<[usize; 3]>,
+    pub material_indices: Vec<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shading {
+    Flat,
+    Smooth,
+}
+
+pub struct TriangleMesh {
+    data: MeshData,
+    materials: Vec<Arc<dyn Material>>,
+    shading: Shading,
+    tree: LinearBvh,
+    // Running total of the face areas, for sampling the mesh as a light.
+    area_cdf: Vec<f64>,
+}
+
+impl TriangleMesh {
+    /// Smooth shading uses the mesh normals, or area-weighted vertex normals
+    /// when the data has none.
+    pub fn new(mut data: MeshData, materials: Vec<Arc<dyn Material>>, shading: Shading) -> Self {
+        assert!(!materials.is_empty(), "A TriangleMesh needs at least one material");
+        let vertex_count = data.positions.len();
+        assert!(data.normals.is_empty() || data.normals.len() == vertex_count, "Expected one normal per vertex");
+        assert!(data.uvs.is_empty() || data.uvs.len() == vertex_count, "Expected one UV per vertex");
+        assert!(
+            data.material_indices.is_empty() || data.material_indices.len() == data.indices.len(),
+            "Expected one material index per face"
+        );
+        assert!(
+            data.indices.iter().flatten().all(|&i| i < vertex_count),
+            "Vertex index out of range"
+        );
+        assert!(
+            data.material_indices.iter().all(|&i| i < materials.len()),
+            "Material index out of range"
+        );
+
+        if shading == Shading::Smooth && data.normals.is_empty() {
+            data.normals = vertex_normals(&data);
+        }
+        let bounds = data
+            .indices
+            .iter()
+            .map(|&[i0, i1, i2]| triangle_bounds(&data.positions[i0], &data.positions[i1], &data.positions[i2]))
+            .collect::<Vec<_>>();
+        let tree = LinearBvh::new(&bounds);
+        let area_cdf = data
+            .indices
+            .iter()
+            .scan(0.0, |total, &[i0, i1, i2]| {
+                let p = &data.positions;
+                *total += 0.5 * (p[i1] - p[i0]).cross(&(p[i2] - p[i0])).magnitude();
+                Some(*total)
+            })
+            .collect();
+        TriangleMesh { data, materials, shading, tree, area_cdf }
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.data.indices.len()
+    }
+
+    pub fn stats(&self) -> BvhStats {
+        self.tree.stats()
+    }
+
+    fn face_vertices(&self, face: usize) -> [Point; 3] {
+        let [i0, i1, i2] = self.data.indices[face];
+        [self.data.positions[i0], self.data.positions[i1], self.data.positions[i2]]
+    }
+
+    fn hit_face(&self, face: usize, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let [i0, i1, i2] = self.data.indices[face];
+        let vertices = self.face_vertices(face);
+        let (t, barycentric) = intersect(ray, &vertices[0], &vertices[1], &vertices[2], t_min, t_max)?;
+
+        let normals = if self.shading == Shading::Smooth {
+            Some([self.data.normals[i0], self.data.normals[i1], self.data.normals[i2]])
+        } else {
+            None
+        };
+        let uvs = if self.data.uvs.is_empty() {
+            [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)]
+        } else {
+            [self.data.uvs[i0], self.data.uvs[i1], self.data.uvs[i2]]
+        };
+        let material = &self.materials[self.data.material_indices.get(face).copied().unwrap_or(0)];
+        Some(hit_record(ray, t, barycentric, &vertices, normals.as_ref(), &uvs, material))
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.tree.hit(ray, t_min, t_max, |face, t_min, t_max| self.hit_face(face, ray, t_min, t_max))
+    }
+
+    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<AABB> {
+        self.tree.bounds()
+    }
+
+    /// Samples points uniformly over the surface of the whole mesh. Every
+    /// face along `direction` could have been picked, not just the nearest.
+    fn pdf_value(&self, origin: &Point, direction: &Vector3<f64>) -> f64 {
+        let total_area = self.area_cdf.last().copied().unwrap_or(0.0);
+        let ray = Ray::new(*origin, *direction, 0.0);
+        let mut pdf = 0.0;
+        self.tree.hit(&ray, 0.001, f64::MAX, |face, t_min, _t_max| {
+            let [v0, v1, v2] = self.face_vertices(face);
+            if let Some((t, _)) = intersect(&ray, &v0, &v1, &v2, t_min, f64::MAX) {
+                pdf += area_pdf(direction, t, &(v1 - v0).cross(&(v2 - v0)), total_area);
+            }
+            // Reporting no hit keeps the traversal visiting every face.
+            None
+        });
+        pdf
+    }
+
+    fn random(&self, origin: &Point) -> Vector3<f64> {
+        let total_area = match self.area_cdf.last() {
+            Some(&total_area) => total_area,
+            // Arbitrary, `pdf_value` is zero for a mesh without faces.
+            None => return Vector3::x(),
+        };
+        let target = random_double() * total_area;
+        let face = self.area_cdf.partition_point(|&area| area <= target).min(self.area_cdf.len() - 1);
+        let [v0, v1, v2] = self.face_vertices(face);
+        sample_triangle(&v0, &v1, &v2) - origin
+    }
+}
+
+fn vertex_normals(data: &MeshData) -> Vec<Vector3<f64>> {
+    let mut normals = vec![Vector3::zeros(); data.positions.len()];
+    for &[i0, i1, i2] in &data.indices {
+        let p = &data.positions;
+        // The cross product's length is twice the face area, which weights
+        // large faces more.
+        let face_normal = (p[i1] - p[i0]).cross(&(p[i2] - p[i0]));
+        normals[i0] += face_normal;
+        normals[i1] += face_normal;
+        normals[i2] += face_normal;
+    }
+    normals
+        .into_iter()
+        .map(|n| n.try_normalize(0.0).unwrap_or_else(Vector3::zeros))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        color::Color,
+        hittable_list::HittableList,
+        material::Lambertian,
+        triangle::Triangle,
+        util::{random_unit_vector, random_vector_within},
+    };
+
+    #[test]
+    fn closest_hits_match_separate_triangles() {
+        let material: Arc<dyn Material> = Arc::new(Lambertian::new(Color::zeros()));
+        let mut data = MeshData {
+            positions: (0..100).map(|_| random_vector_within(-2.0, 2.0)).collect(),
+            ..MeshData::default()
+        };
+        while data.indices.len() < 300 {
+            let face = [0, 1, 2].map(|_| ((random_double() * 100.0) as usize).min(99));
+            if face[0] != face[1] && face[1] != face[2] && face[2] != face[0] {
+                data.indices.push(face);
+            }
+        }
+        let mut triangles = HittableList::default();
+        for &[i0, i1, i2] in &data.indices {
+            let p = &data.positions;
+            triangles.push(Triangle::new(p[i0], p[i1], p[i2], material.clone()));
+        }
+        let mesh = TriangleMesh::new(data, vec![material], Shading::Flat);
+
+        let mut hits = 0;
+        for _ in 0..1000 {
+            let ray = Ray::new(random_vector_within(-3.0, 3.0), random_unit_vector(), 0.0);
+            for t_max in [1.5, f64::INFINITY] {
+                let expected = triangles.hit(&ray, 0.001, t_max).map(|hit| (hit.t, hit.normal, hit.front_face));
+                let found = mesh.hit(&ray, 0.001, t_max).map(|hit| (hit.t, hit.normal, hit.front_face));
+                assert_eq!(found, expected, "{:?} {:?} up to {t_max}", ray.origin(), ray.direction());
+                hits += expected.is_some() as usize;
+            }
+        }
+        assert!(hits > 100, "only {hits} rays hit anything");
+    }
+}
@@ -2,12 +2,37 @@ pub mod color;
 pub mod point;
 pub mod ray;
 pub mod sphere;
+pub mod moving_sphere;
 pub mod hittable;
 pub mod hittable_list;
 pub mod camera;
 pub mod material;
 pub mod renderer;
+pub mod roulette;
+pub mod integrator;
+pub mod scene;
+pub mod light;
+pub mod onb;
+pub mod pdf;
 pub mod aabb;
 pub mod rect;
 pub mod cube;
-mod util;
\ No newline at end of file
+pub mod constant_medium;
+pub mod volume;
+pub mod transform;
+pub mod instance;
+pub mod bvh;
+pub mod sah_bvh;
+pub mod triangle;
+pub mod mesh;
+pub mod load_error;
+pub mod obj;
+pub mod ply;
+pub mod gltf;
+pub mod background;
+pub mod environment;
+pub mod texture;
+pub mod normal_map;
+pub mod noise;
+mod util;#[cfg(test)]
+mod test_util;
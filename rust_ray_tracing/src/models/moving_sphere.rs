@@ -0,0 +1,61 @@
+use std::sync::Arc;
+
+use nalgebra::Vector3;
+
+use super::{
+    aabb::{surrounding_box, AABB},
+    hittable::{HitRecord, Hittable},
+    material::Material,
+    ray::Ray,
+    sphere::hit_sphere,
+};
+
+/// Sphere whose center moves linearly from `center0` at `time0` to
+/// `center1` at `time1`, and keeps moving along that line outside it.
+///
+/// It cannot be sampled as a light: `pdf_value` and `random` are not given
+/// the ray time, so they keep the defaults of zero density and an
+/// arbitrary direction.
+pub struct MovingSphere {
+    center0: Vector3<f64>,
+    center1: Vector3<f64>,
+    time0: f64,
+    time1: f64,
+    radius: f64,
+    material: Arc<dyn Material>,
+}
+
+impl MovingSphere {
+    pub fn new(
+        center0: Vector3<f64>,
+        center1: Vector3<f64>,
+        time0: f64,
+        time1: f64,
+        radius: f64,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        MovingSphere { center0, center1, time0, time1, radius, material }
+    }
+
+    pub fn center(&self, time: f64) -> Vector3<f64> {
+        if self.time1 == self.time0 {
+            return self.center0;
+        }
+        self.center0 + (time - self.time0) / (self.time1 - self.time0) * (self.center1 - self.center0)
+    }
+}
+
+impl Hittable for MovingSphere {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        hit_sphere(&self.center(ray.time()), self.radius, &self.material, ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<AABB> {
+        let radius = Vector3::new(self.radius, self.radius, self.radius);
+        let (start, end) = (self.center(time0), self.center(time1));
+        Some(surrounding_box(
+            &AABB::new(start - radius, start + radius),
+            &AABB::new(end - radius, end + radius),
+        ))
+    }
+}
@@ -0,0 +1,121 @@
+use nalgebra::Vector3;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use super::point::Point;
+
+const POINT_COUNT: usize = 256;
+
+fn permutation(rng: &mut StdRng) -> Vec<usize> {
+    let mut perm = (0..POINT_COUNT).collect::<Vec<_>>();
+    perm.shuffle(rng);
+    perm
+}
+
+/// Gradient noise with values in about [-1, 1]. The same seed always
+/// produces the same noise.
+pub struct Perlin {
+    gradients: Vec<Vector3<f64>>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let gradients = (0..POINT_COUNT)
+            .map(|_| {
+                Vector3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0))
+                    .normalize()
+            })
+            .collect();
+        Perlin {
+            gradients,
+            perm_x: permutation(&mut rng),
+            perm_y: permutation(&mut rng),
+            perm_z: permutation(&mut rng),
+        }
+    }
+
+    pub fn noise(&self, p: &Point) -> f64 {
+        let cell = p.map(f64::floor);
+        let f = p - cell;
+        // Hermite smoothing hides the grid in the interpolation.
+        let s = f.map(|t| t * t * (3.0 - 2.0 * t));
+        let (i, j, k) = (cell.x as i64, cell.y as i64, cell.z as i64);
+
+        let mut accum = 0.0;
+        for di in 0..2 {
+            for dj in 0..2 {
+                for dk in 0..2 {
+                    let gradient = &self.gradients[self.perm_x[((i + di) & 255) as usize]
+                        ^ self.perm_y[((j + dj) & 255) as usize]
+                        ^ self.perm_z[((k + dk) & 255) as usize]];
+                    let (a, b, c) = (di as f64, dj as f64, dk as f64);
+                    let weight = Vector3::new(f.x - a, f.y - b, f.z - c);
+                    accum += (a * s.x + (1.0 - a) * (1.0 - s.x))
+                        * (b * s.y + (1.0 - b) * (1.0 - s.y))
+                        * (c * s.z + (1.0 - c) * (1.0 - s.z))
+                        * gradient.dot(&weight);
+                }
+            }
+        }
+        accum
+    }
+
+    /// Sum of `octaves` layers of noise, each at twice the frequency and
+    /// half the amplitude of the previous one.
+    pub fn turbulence(&self, p: &Point, octaves: usize) -> f64 {
+        let mut accum = 0.0;
+        let mut p = *p;
+        let mut weight = 1.0;
+        for _ in 0..octaves {
+            accum += weight * self.noise(&p);
+            weight *= 0.5;
+            p *= 2.0;
+        }
+        accum.abs()
+    }
+}
+
+/// Cellular (Worley) noise with one jittered feature point per unit cell.
+pub struct Worley {
+    features: Vec<Vector3<f64>>,
+    perm: Vec<usize>,
+}
+
+impl Worley {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let features = (0..POINT_COUNT)
+            .map(|_| Vector3::new(rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0)))
+            .collect();
+        Worley { features, perm: permutation(&mut rng) }
+    }
+
+    /// Distances from `p` to the nearest and second nearest feature points.
+    pub fn distances(&self, p: &Point) -> (f64, f64) {
+        let cell = p.map(f64::floor);
+        let (mut nearest, mut second) = (f64::MAX, f64::MAX);
+        for di in -1..=1 {
+            for dj in -1..=1 {
+                for dk in -1..=1 {
+                    let neighbour = cell + Vector3::new(di as f64, dj as f64, dk as f64);
+                    let distance = (neighbour + self.feature(&neighbour) - p).magnitude();
+                    if distance < nearest {
+                        second = nearest;
+                        nearest = distance;
+                    } else if distance < second {
+                        second = distance;
+                    }
+                }
+            }
+        }
+        (nearest, second)
+    }
+
+    fn feature(&self, cell: &Vector3<f64>) -> Vector3<f64> {
+        let hash = |h: usize, c: f64| self.perm[(h + (c as i64 & 255) as usize) & 255];
+        self.features[hash(hash(hash(0, cell.x), cell.y), cell.z)]
+    }
+}
@@ -0,0 +1,151 @@
+use std::sync::Arc;
+
+use nalgebra::Vector3;
+
+use super::{
+    color::Color,
+    hittable::HitRecord,
+    material::{Material, ScatterRecord},
+    point::Point,
+    ray::Ray,
+    texture::Texture,
+};
+
+/// Perturbs the shading normal of `material` with a tangent space normal
+/// map, whose red, green and blue channels in [0, 1] hold the normal's
+/// components along the u tangent, the v tangent and the surface normal.
+pub struct NormalMapped {
+    pub material: Arc<dyn Material>,
+    pub normal_map: Arc<dyn Texture>,
+}
+
+impl NormalMapped {
+    fn shading_normal(&self, hit_record: &HitRecord) -> Vector3<f64> {
+        let (tangent, bitangent) = tangent_frame(hit_record);
+        let mapped = 2.0 * self.normal_map.value(hit_record.u, hit_record.v, &hit_record.p) - Vector3::new(1.0, 1.0, 1.0);
+        mapped.x * tangent + mapped.y * bitangent + mapped.z * hit_record.normal
+    }
+}
+
+impl Material for NormalMapped {
+    fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord> {
+        scatter_with_normal(self.material.as_ref(), ray_in, hit_record, self.shading_normal(hit_record))
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> f64 {
+        let normal = self.shading_normal(hit_record);
+        scattering_pdf_with_normal(self.material.as_ref(), ray_in, hit_record, scattered, normal)
+    }
+
+    fn emitted(&self, u: f64, v: f64, p: &Point) -> Color {
+        self.material.emitted(u, v, p)
+    }
+}
+
+/// Perturbs the shading normal of `material` as if the surface were
+/// displaced along its normal by `scale` times the first channel of `height`.
+pub struct BumpMapped {
+    pub material: Arc<dyn Material>,
+    pub height: Arc<dyn Texture>,
+    pub scale: f64,
+}
+
+// Step in texture space for the finite differences of the height.
+const BUMP_DELTA: f64 = 0.0005;
+
+impl BumpMapped {
+    fn shading_normal(&self, hit_record: &HitRecord) -> Vector3<f64> {
+        let (u, v, p) = (hit_record.u, hit_record.v, hit_record.p);
+        let height = self.height.value(u, v, &p).x;
+        let height_u = self.height.value(u + BUMP_DELTA, v, &(p + BUMP_DELTA * hit_record.dpdu)).x;
+        let height_v = self.height.value(u, v + BUMP_DELTA, &(p + BUMP_DELTA * hit_record.dpdv)).x;
+
+        let n = hit_record.normal;
+        let dpdu = hit_record.dpdu + self.scale * (height_u - height) / BUMP_DELTA * n;
+        let dpdv = hit_record.dpdv + self.scale * (height_v - height) / BUMP_DELTA * n;
+        match dpdu.cross(&dpdv).try_normalize(1e-12) {
+            Some(normal) if normal.dot(&n) < 0.0 => -normal,
+            Some(normal) => normal,
+            None => n,
+        }
+    }
+}
+
+impl Material for BumpMapped {
+    fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord> {
+        scatter_with_normal(self.material.as_ref(), ray_in, hit_record, self.shading_normal(hit_record))
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> f64 {
+        let normal = self.shading_normal(hit_record);
+        scattering_pdf_with_normal(self.material.as_ref(), ray_in, hit_record, scattered, normal)
+    }
+
+    fn emitted(&self, u: f64, v: f64, p: &Point) -> Color {
+        self.material.emitted(u, v, p)
+    }
+}
+
+/// Unit tangents along u and v, orthogonal to the hit's normal. Surfaces
+/// without usable texture derivatives get an arbitrary frame.
+fn tangent_frame(hit_record: &HitRecord) -> (Vector3<f64>, Vector3<f64>) {
+    let n = hit_record.normal;
+    let tangent = (hit_record.dpdu - n.dot(&hit_record.dpdu) * n)
+        .try_normalize(1e-12)
+        .unwrap_or_else(|| {
+            let axis = if n.x.abs() > 0.9 { Vector3::y() } else { Vector3::x() };
+            n.cross(&axis).normalize()
+        });
+    let bitangent = n.cross(&tangent);
+    // Mirrored texture coordinates flip the handedness of the frame.
+    if bitangent.dot(&hit_record.dpdv) < 0.0 {
+        (tangent, -bitangent)
+    } else {
+        (tangent, bitangent)
+    }
+}
+
+/// Copy of the hit with `normal` as its shading normal, unless it points
+/// to the other side of the surface.
+fn with_normal(hit_record: &HitRecord, normal: Vector3<f64>) -> HitRecord {
+    let mut shaded = hit_record.clone();
+    if let Some(normal) = normal.try_normalize(1e-12).filter(|normal| normal.dot(&hit_record.normal) > 0.0) {
+        shaded.normal = normal;
+    }
+    shaded
+}
+
+/// Whether the shading normal puts `direction` on the other side of the
+/// surface than the hit's own normal does. Such directions would leak
+/// through the surface so they are absorbed instead.
+fn leaks(direction: &Vector3<f64>, shaded: &HitRecord, hit_record: &HitRecord) -> bool {
+    (direction.dot(&shaded.normal) > 0.0) != (direction.dot(&hit_record.normal) > 0.0)
+}
+
+fn scatter_with_normal(
+    material: &dyn Material,
+    ray_in: &Ray,
+    hit_record: &HitRecord,
+    normal: Vector3<f64>,
+) -> Option<ScatterRecord> {
+    let shaded = with_normal(hit_record, normal);
+    match material.scatter(ray_in, &shaded)? {
+        ScatterRecord::Specular { ray, .. } if leaks(&ray.direction(), &shaded, hit_record) => None,
+        record => Some(record),
+    }
+}
+
+fn scattering_pdf_with_normal(
+    material: &dyn Material,
+    ray_in: &Ray,
+    hit_record: &HitRecord,
+    scattered: &Ray,
+    normal: Vector3<f64>,
+) -> f64 {
+    let shaded = with_normal(hit_record, normal);
+    if leaks(&scattered.direction(), &shaded, hit_record) {
+        0.0
+    } else {
+        material.scattering_pdf(ray_in, &shaded, scattered)
+    }
+}
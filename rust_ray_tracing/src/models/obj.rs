@@ -0,0 +1,311 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+    sync::Arc,
+};
+
+use nalgebra::Vector3;
+
+use super::{
+    color::Color,
+    load_error::{LineContext, LoadError},
+    material::{Dielectric, DiffuseLight, Lambertian, Material, Metal},
+    mesh::{MeshData, Shading, TriangleMesh},
+    point::Point,
+};
+
+/// Loads a Wavefront OBJ file into a single `TriangleMesh`.
+///
+/// Polygons are triangulated as fans and `usemtl` switches the material of
+/// the following faces to one read from the `mtllib` files next to the OBJ.
+/// Faces before any `usemtl`, or naming an unknown material, use
+/// `default_material`. The mesh is smooth shaded when the file provides a
+/// normal for every face vertex. A file without faces is a
+/// `LoadError::Format`, as an empty mesh has nothing to render or bound.
+pub fn load_obj(path: impl AsRef<Path>, default_material: Arc<dyn Material>) -> Result<TriangleMesh, LoadError> {
+    let path = path.as_ref();
+    let file_name = path.display().to_string();
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+    let reader = BufReader::new(File::open(path)?);
+
+    let mut positions: Vec<Point> = Vec::new();
+    let mut uvs: Vec<(f64, f64)> = Vec::new();
+    let mut normals: Vec<Vector3<f64>> = Vec::new();
+
+    let mut library: HashMap<String, MtlMaterial> = HashMap::new();
+    let mut materials = vec![default_material];
+    let mut material_slots: HashMap<String, usize> = HashMap::new();
+    let mut current_material = 0;
+
+    let mut vertex_slots: HashMap<(usize, Option<usize>, Option<usize>), usize> = HashMap::new();
+    let mut vertices: Vec<(usize, Option<usize>, Option<usize>)> = Vec::new();
+    let mut data = MeshData::default();
+
+    for (line_index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let context = LineContext { file: &file_name, line: line_index + 1 };
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("v") => positions.push(parse_vector(&context, &mut tokens)?),
+            Some("vn") => normals.push(parse_vector(&context, &mut tokens)?),
+            Some("vt") => {
+                let u = context.parse(tokens.next())?;
+                let v = tokens.next().map_or(Ok(0.0), |v| context.parse(Some(v)))?;
+                uvs.push((u, v));
+            }
+            Some("f") => {
+                let mut face = Vec::new();
+                for token in tokens {
+                    let key = parse_face_vertex(&context, token, positions.len(), uvs.len(), normals.len())?;
+                    let slot = *vertex_slots.entry(key).or_insert_with(|| {
+                        vertices.push(key);
+                        vertices.len() - 1
+                    });
+                    face.push(slot);
+                }
+                if face.len() < 3 {
+                    return Err(context.error("face needs at least three vertices"));
+                }
+                for i in 1..face.len() - 1 {
+                    data.indices.push([face[0], face[i], face[i + 1]]);
+                    data.material_indices.push(current_material);
+                }
+            }
+            Some("mtllib") => {
+                for name in tokens {
+                    let mtl_path = directory.join(name);
+                    let mtl_file = File::open(&mtl_path).map_err(|error| {
+                        context.error(format!("cannot open material library '{}': {error}", mtl_path.display()))
+                    })?;
+                    library.extend(parse_mtl(BufReader::new(mtl_file), &mtl_path.display().to_string())?);
+                }
+            }
+            Some("usemtl") => {
+                let name = tokens.collect::<Vec<_>>().join(" ");
+                current_material = match material_slots.get(&name) {
+                    Some(&slot) => slot,
+                    None => match library.get(&name) {
+                        Some(mtl) => {
+                            materials.push(mtl.to_material());
+                            material_slots.insert(name, materials.len() - 1);
+                            materials.len() - 1
+                        }
+                        None => 0,
+                    },
+                };
+            }
+            _ => {}
+        }
+    }
+
+    if data.indices.is_empty() {
+        return Err(LoadError::Format { file: file_name, message: "no faces".to_string() });
+    }
+    data.positions = vertices.iter().map(|&(v, _, _)| positions[v]).collect();
+    if vertices.iter().any(|&(_, vt, _)| vt.is_some()) {
+        data.uvs = vertices.iter().map(|&(_, vt, _)| vt.map_or((0.0, 0.0), |vt| uvs[vt])).collect();
+    }
+    let has_normals = !vertices.is_empty() && vertices.iter().all(|&(_, _, vn)| vn.is_some());
+    if has_normals {
+        data.normals = vertices.iter().filter_map(|&(_, _, vn)| vn.map(|vn| normals[vn])).collect();
+    }
+    let shading = if has_normals { Shading::Smooth } else { Shading::Flat };
+    Ok(TriangleMesh::new(data, materials, shading))
+}
+
+fn parse_vector<'a>(context: &LineContext, tokens: &mut impl Iterator<Item = &'a str>) -> Result<Vector3<f64>, LoadError> {
+    Ok(Vector3::new(
+        context.parse(tokens.next())?,
+        context.parse(tokens.next())?,
+        context.parse(tokens.next())?,
+    ))
+}
+
+/// Parses `v`, `v/vt`, `v//vn` or `v/vt/vn` into zero-based indices.
+fn parse_face_vertex(
+    context: &LineContext,
+    token: &str,
+    position_count: usize,
+    uv_count: usize,
+    normal_count: usize,
+) -> Result<(usize, Option<usize>, Option<usize>), LoadError> {
+    let mut parts = token.split('/');
+    let v = resolve_index(context, parts.next(), position_count)?;
+    let vt = match parts.next() {
+        Some("") | None => None,
+        Some(part) => Some(resolve_index(context, Some(part), uv_count)?),
+    };
+    let vn = match parts.next() {
+        Some("") | None => None,
+        Some(part) => Some(resolve_index(context, Some(part), normal_count)?),
+    };
+    Ok((v, vt, vn))
+}
+
+fn resolve_index(context: &LineContext, token: Option<&str>, count: usize) -> Result<usize, LoadError> {
+    let index: i64 = context.parse(token)?;
+    let resolved = if index > 0 { index - 1 } else { count as i64 + index };
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(context.error(format!("index {index} out of range")));
+    }
+    Ok(resolved as usize)
+}
+
+struct MtlMaterial {
+    diffuse: Color,
+    specular: Color,
+    emission: Color,
+    shininess: f64,
+    index_of_refraction: f64,
+    dissolve: f64,
+    illum: i32,
+}
+
+impl Default for MtlMaterial {
+    fn default() -> Self {
+        MtlMaterial {
+            diffuse: Color::new(0.8, 0.8, 0.8),
+            specular: Color::zeros(),
+            emission: Color::zeros(),
+            shininess: 0.0,
+            index_of_refraction: 1.0,
+            dissolve: 1.0,
+            illum: 2,
+        }
+    }
+}
+
+impl MtlMaterial {
+    /// Closest built-in material: emissive surfaces become `DiffuseLight`,
+    /// transparent ones `Dielectric`, mirror-like ones `Metal` with a fuzz
+    /// derived from `Ns` and everything else `Lambertian`.
+    fn to_material(&self) -> Arc<dyn Material> {
+        if self.emission.max() > 0.0 {
+            return Arc::new(DiffuseLight::new(self.emission));
+        }
+        let transparent = self.dissolve < 1.0 || matches!(self.illum, 4 | 6 | 7 | 9);
+        let mirror = self.illum == 3 || self.specular.max() > self.diffuse.max();
+        if transparent {
+            let index_of_refraction = if self.index_of_refraction > 1.0 { self.index_of_refraction } else { 1.5 };
+            Arc::new(Dielectric { index_of_refraction })
+        } else if mirror {
+            let fuzz = (2.0 / (self.shininess + 2.0)).sqrt();
+            Arc::new(Metal::new(self.specular, fuzz))
+        } else {
+            Arc::new(Lambertian::new(self.diffuse))
+        }
+    }
+}
+
+fn parse_mtl(reader: impl BufRead, file_name: &str) -> Result<HashMap<String, MtlMaterial>, LoadError> {
+    let mut library = HashMap::new();
+    let mut current: Option<(String, MtlMaterial)> = None;
+    for (line_index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let context = LineContext { file: file_name, line: line_index + 1 };
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        if keyword == "newmtl" {
+            if let Some((name, material)) = current.take() {
+                library.insert(name, material);
+            }
+            current = Some((tokens.collect::<Vec<_>>().join(" "), MtlMaterial::default()));
+            continue;
+        }
+        let material = match current.as_mut() {
+            Some((_, material)) => material,
+            None if keyword.starts_with('#') => continue,
+            None => return Err(context.error(format!("'{keyword}' before any newmtl"))),
+        };
+        match keyword {
+            "Kd" => material.diffuse = parse_vector(&context, &mut tokens)?,
+            "Ks" => material.specular = parse_vector(&context, &mut tokens)?,
+            "Ke" => material.emission = parse_vector(&context, &mut tokens)?,
+            "Ns" => material.shininess = context.parse(tokens.next())?,
+            "Ni" => material.index_of_refraction = context.parse(tokens.next())?,
+            "d" => material.dissolve = context.parse(tokens.next())?,
+            "Tr" => material.dissolve = 1.0 - context.parse::<f64>(tokens.next())?,
+            "illum" => material.illum = context.parse(tokens.next())?,
+            _ => {}
+        }
+    }
+    if let Some((name, material)) = current {
+        library.insert(name, material);
+    }
+    Ok(library)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{hittable::Hittable, ray::Ray, test_util::TempFile};
+
+    fn load(name: &str, contents: &str) -> Result<TriangleMesh, LoadError> {
+        let file = TempFile::new(name, contents);
+        load_obj(file.path(), Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))))
+    }
+
+    /// Casts a ray straight down -z onto (x, y).
+    fn hit_from_above(mesh: &TriangleMesh, x: f64, y: f64) -> Option<f64> {
+        let ray = Ray::new(Point::new(x, y, 5.0), Vector3::new(0.0, 0.0, -1.0), 0.0);
+        mesh.hit(&ray, 0.001, f64::INFINITY).map(|hit| hit.t)
+    }
+
+    #[test]
+    fn quad_is_triangulated_as_a_fan() {
+        let mesh = load("quad.obj", "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\n").unwrap();
+        assert_eq!(mesh.triangle_count(), 2);
+        assert_eq!(hit_from_above(&mesh, 0.75, 0.25), Some(5.0));
+        assert_eq!(hit_from_above(&mesh, 0.25, 0.75), Some(5.0));
+    }
+
+    #[test]
+    fn negative_indices_count_back_from_the_last_vertex() {
+        let source = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 0 2\nv 1 0 2\nv 0 1 2\nf -3 -2 -1\n";
+        let mesh = load("negative.obj", source).unwrap();
+        assert_eq!(mesh.triangle_count(), 1);
+        assert_eq!(hit_from_above(&mesh, 0.2, 0.2), Some(3.0));
+    }
+
+    #[test]
+    fn bad_float_reports_its_line() {
+        let file = TempFile::new("bad_float.obj", "v 0 0 0\nv 1 x 0\n");
+        let error = load_obj(file.path(), Arc::new(Lambertian::new(Color::zeros()))).err().unwrap();
+        assert!(matches!(error, LoadError::Parse { line: 2, .. }));
+        assert!(error.to_string().starts_with(&format!("{}:2:", file.path().display())), "{error}");
+    }
+
+    #[test]
+    fn out_of_range_index_is_an_error() {
+        let error = load("out_of_range.obj", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4\n").err().unwrap();
+        match error {
+            LoadError::Parse { line, message, .. } => {
+                assert_eq!(line, 4);
+                assert!(message.contains("out of range"), "{message}");
+            }
+            other => panic!("unexpected error {other}"),
+        }
+    }
+
+    #[test]
+    fn file_without_faces_is_a_format_error() {
+        let error = load("points.obj", "v 0 0 0\nv 1 0 0\nv 0 1 0\n").err().unwrap();
+        assert!(matches!(error, LoadError::Format { .. }), "{error}");
+    }
+
+    #[test]
+    fn emissive_mtl_material_becomes_a_light() {
+        let mtl = TempFile::new("lamp.mtl", "newmtl lamp\nKd 0 0 0\nKe 4 3 2\n");
+        let source = format!("mtllib {}\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl lamp\nf 1 2 3\n", mtl.file_name());
+        let mesh = load("lamp.obj", &source).unwrap();
+        let ray = Ray::new(Point::new(0.2, 0.2, 5.0), Vector3::new(0.0, 0.0, -1.0), 0.0);
+        let hit = mesh.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert_eq!(hit.material.emitted(hit.u, hit.v, &hit.p), Color::new(4.0, 3.0, 2.0));
+        assert!(hit.material.scatter(&ray, &hit).is_none());
+    }
+}
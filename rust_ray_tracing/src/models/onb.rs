@@ -0,0 +1,23 @@
+use nalgebra::Vector3;
+
+/// Orthonormal basis with `w` along a given direction.
+pub struct Onb {
+    pub u: Vector3<f64>,
+    pub v: Vector3<f64>,
+    pub w: Vector3<f64>,
+}
+
+impl Onb {
+    pub fn from_w(n: &Vector3<f64>) -> Self {
+        let w = n.normalize();
+        let a = if w.x.abs() > 0.9 { Vector3::y() } else { Vector3::x() };
+        let v = w.cross(&a).normalize();
+        let u = w.cross(&v);
+        Onb { u, v, w }
+    }
+
+    /// Converts coordinates in this basis to world space.
+    pub fn local(&self, a: &Vector3<f64>) -> Vector3<f64> {
+        a.x * self.u + a.y * self.v + a.z * self.w
+    }
+}
@@ -0,0 +1,91 @@
+use std::f64::consts::PI;
+
+use nalgebra::Vector3;
+
+use super::{
+    onb::Onb,
+    util::{random_cosine_direction, random_double, random_unit_vector},
+};
+
+/// Probability density over directions that can also be sampled.
+pub trait Pdf: Send + Sync {
+    /// Solid angle density of `direction`.
+    fn value(&self, direction: &Vector3<f64>) -> f64;
+
+    fn generate(&self) -> Vector3<f64>;
+}
+
+/// Cosine-weighted hemisphere around a normal.
+pub struct CosinePdf {
+    uvw: Onb,
+}
+
+impl CosinePdf {
+    pub fn new(normal: &Vector3<f64>) -> Self {
+        CosinePdf { uvw: Onb::from_w(normal) }
+    }
+}
+
+impl Pdf for CosinePdf {
+    fn value(&self, direction: &Vector3<f64>) -> f64 {
+        let cosine = direction.normalize().dot(&self.uvw.w);
+        (cosine / PI).max(0.0)
+    }
+
+    fn generate(&self) -> Vector3<f64> {
+        self.uvw.local(&random_cosine_direction())
+    }
+}
+
+/// Every direction equally likely.
+pub struct SpherePdf;
+
+impl Pdf for SpherePdf {
+    fn value(&self, _direction: &Vector3<f64>) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+
+    fn generate(&self) -> Vector3<f64> {
+        random_unit_vector()
+    }
+}
+
+/// Henyey-Greenstein phase function around a propagation direction. `g` in
+/// (-1, 1) is the mean cosine of the scattering angle: positive values
+/// scatter forward, negative ones back and zero in every direction.
+pub struct HenyeyGreensteinPdf {
+    uvw: Onb,
+    g: f64,
+}
+
+impl HenyeyGreensteinPdf {
+    pub fn new(direction: &Vector3<f64>, g: f64) -> Self {
+        HenyeyGreensteinPdf { uvw: Onb::from_w(direction), g }
+    }
+}
+
+impl Pdf for HenyeyGreensteinPdf {
+    fn value(&self, direction: &Vector3<f64>) -> f64 {
+        henyey_greenstein(direction.normalize().dot(&self.uvw.w), self.g)
+    }
+
+    fn generate(&self) -> Vector3<f64> {
+        let g = self.g;
+        let (r1, r2) = (random_double(), random_double());
+        let cos_theta = if g.abs() < 1e-3 {
+            1.0 - 2.0 * r1
+        } else {
+            let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * r1);
+            ((1.0 + g * g - s * s) / (2.0 * g)).clamp(-1.0, 1.0)
+        };
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        let phi = 2.0 * PI * r2;
+        self.uvw.local(&Vector3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta))
+    }
+}
+
+/// Density of scattering by an angle with cosine `cos_theta`.
+pub(crate) fn henyey_greenstein(cos_theta: f64, g: f64) -> f64 {
+    let denominator = 1.0 + g * g - 2.0 * g * cos_theta;
+    (1.0 - g * g) / (4.0 * PI * denominator * denominator.sqrt())
+}
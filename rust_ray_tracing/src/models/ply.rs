@@ -0,0 +1,450 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+    sync::Arc,
+};
+
+use nalgebra::Vector3;
+
+use super::{
+    load_error::{LineContext, LoadError},
+    material::Material,
+    mesh::{MeshData, Shading, TriangleMesh},
+};
+
+/// Loads a PLY file into a `TriangleMesh` using `material` for every face.
+/// A file without faces, such as a point cloud, is a `LoadError::Format`.
+pub fn load_ply(path: impl AsRef<Path>, material: Arc<dyn Material>, shading: Shading) -> Result<TriangleMesh, LoadError> {
+    let path = path.as_ref();
+    let data = read_ply(path)?;
+    if data.indices.is_empty() {
+        return Err(LoadError::Format { file: path.display().to_string(), message: "no faces".to_string() });
+    }
+    Ok(TriangleMesh::new(data, vec![material], shading))
+}
+
+/// Reads the `vertex` and `face` elements of an ASCII or binary PLY file.
+/// Per-vertex normals (`nx`, `ny`, `nz`), texture coordinates (`u`/`v`,
+/// `s`/`t`, `texture_u`/`texture_v` or `texture_s`/`texture_t`) are read
+/// when present, polygons are triangulated as fans and any other element
+/// or property, vertex colors included, is skipped.
+pub fn read_ply(path: impl AsRef<Path>) -> Result<MeshData, LoadError> {
+    let path = path.as_ref();
+    let file_name = path.display().to_string();
+    let mut reader = BufReader::new(File::open(path)?);
+    let (encoding, elements, header_lines) = read_header(&mut reader, &file_name)?;
+    let mut body = BodyReader {
+        reader,
+        encoding,
+        file: &file_name,
+        line: header_lines,
+        tokens: Vec::new(),
+        next_token: 0,
+    };
+
+    let mut data = MeshData::default();
+    for element in &elements {
+        match element.name.as_str() {
+            "vertex" => read_vertices(&mut body, element, &mut data)?,
+            "face" => read_faces(&mut body, element, &mut data)?,
+            _ => {
+                for _ in 0..element.count {
+                    body.start_row()?;
+                    for property in &element.properties {
+                        body.read_property(property)?;
+                    }
+                }
+            }
+        }
+    }
+
+    let vertex_count = data.positions.len();
+    if let Some(index) = data.indices.iter().flatten().find(|&&i| i >= vertex_count) {
+        return Err(LoadError::Format {
+            file: file_name,
+            message: format!("face references vertex {index} but there are only {vertex_count}"),
+        });
+    }
+    Ok(data)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    Ascii,
+    LittleEndian,
+    BigEndian,
+}
+
+#[derive(Clone, Copy)]
+enum ScalarType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl ScalarType {
+    fn parse(context: &LineContext, token: Option<&str>) -> Result<Self, LoadError> {
+        match token {
+            Some("char") | Some("int8") => Ok(ScalarType::I8),
+            Some("uchar") | Some("uint8") => Ok(ScalarType::U8),
+            Some("short") | Some("int16") => Ok(ScalarType::I16),
+            Some("ushort") | Some("uint16") => Ok(ScalarType::U16),
+            Some("int") | Some("int32") => Ok(ScalarType::I32),
+            Some("uint") | Some("uint32") => Ok(ScalarType::U32),
+            Some("float") | Some("float32") => Ok(ScalarType::F32),
+            Some("double") | Some("float64") => Ok(ScalarType::F64),
+            Some(other) => Err(context.error(format!("unknown property type '{other}'"))),
+            None => Err(context.error("missing property type")),
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            ScalarType::I8 | ScalarType::U8 => 1,
+            ScalarType::I16 | ScalarType::U16 => 2,
+            ScalarType::I32 | ScalarType::U32 | ScalarType::F32 => 4,
+            ScalarType::F64 => 8,
+        }
+    }
+}
+
+enum Property {
+    Scalar { name: String, ty: ScalarType },
+    List { name: String, count: ScalarType, item: ScalarType },
+}
+
+impl Property {
+    fn name(&self) -> &str {
+        match self {
+            Property::Scalar { name, .. } | Property::List { name, .. } => name,
+        }
+    }
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+fn read_header(reader: &mut impl BufRead, file_name: &str) -> Result<(Encoding, Vec<Element>, usize), LoadError> {
+    let mut encoding = None;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut line_number = 0;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        line_number += 1;
+        let context = LineContext { file: file_name, line: line_number };
+        if reader.read_line(&mut line)? == 0 {
+            return Err(context.error("missing end_header"));
+        }
+        let mut tokens = line.split_whitespace();
+        let keyword = tokens.next();
+        if line_number == 1 {
+            if keyword != Some("ply") {
+                return Err(context.error("not a PLY file"));
+            }
+            continue;
+        }
+        match keyword {
+            Some("format") => {
+                encoding = Some(match tokens.next() {
+                    Some("ascii") => Encoding::Ascii,
+                    Some("binary_little_endian") => Encoding::LittleEndian,
+                    Some("binary_big_endian") => Encoding::BigEndian,
+                    other => return Err(context.error(format!("unknown format '{}'", other.unwrap_or("")))),
+                });
+            }
+            Some("element") => {
+                let name = tokens.next().ok_or_else(|| context.error("missing element name"))?;
+                elements.push(Element {
+                    name: name.to_string(),
+                    count: context.parse(tokens.next())?,
+                    properties: Vec::new(),
+                });
+            }
+            Some("property") => {
+                let element = elements
+                    .last_mut()
+                    .ok_or_else(|| context.error("property before any element"))?;
+                let property = if tokens.clone().next() == Some("list") {
+                    tokens.next();
+                    let count = ScalarType::parse(&context, tokens.next())?;
+                    let item = ScalarType::parse(&context, tokens.next())?;
+                    let name = tokens.next().ok_or_else(|| context.error("missing property name"))?;
+                    Property::List { name: name.to_string(), count, item }
+                } else {
+                    let ty = ScalarType::parse(&context, tokens.next())?;
+                    let name = tokens.next().ok_or_else(|| context.error("missing property name"))?;
+                    Property::Scalar { name: name.to_string(), ty }
+                };
+                element.properties.push(property);
+            }
+            Some("end_header") => {
+                let encoding = encoding.ok_or_else(|| context.error("missing format line"))?;
+                return Ok((encoding, elements, line_number));
+            }
+            Some("comment") | Some("obj_info") | None => {}
+            Some(other) => return Err(context.error(format!("unexpected header line '{other}'"))),
+        }
+    }
+}
+
+fn read_vertices<R: BufRead>(body: &mut BodyReader<R>, element: &Element, data: &mut MeshData) -> Result<(), LoadError> {
+    let has = |names: &[&str]| names.iter().all(|name| element.properties.iter().any(|p| p.name() == *name));
+    if !has(&["x", "y", "z"]) {
+        return Err(LoadError::Format {
+            file: body.file.to_string(),
+            message: "vertex element has no x, y and z properties".to_string(),
+        });
+    }
+    let has_normals = has(&["nx", "ny", "nz"]);
+    let has_uvs = has(&["u", "v"]) || has(&["s", "t"]) || has(&["texture_u", "texture_v"]) || has(&["texture_s", "texture_t"]);
+
+    for _ in 0..element.count {
+        body.start_row()?;
+        let mut position = Vector3::zeros();
+        let mut normal = Vector3::zeros();
+        let mut uv = (0.0, 0.0);
+        for property in &element.properties {
+            let value = body.read_property(property)?;
+            match property {
+                Property::Scalar { name, .. } => match name.as_str() {
+                    "x" => position.x = value,
+                    "y" => position.y = value,
+                    "z" => position.z = value,
+                    "nx" => normal.x = value,
+                    "ny" => normal.y = value,
+                    "nz" => normal.z = value,
+                    "u" | "s" | "texture_u" | "texture_s" => uv.0 = value,
+                    "v" | "t" | "texture_v" | "texture_t" => uv.1 = value,
+                    _ => {}
+                },
+                Property::List { .. } => {}
+            }
+        }
+        data.positions.push(position);
+        if has_normals {
+            data.normals.push(normal);
+        }
+        if has_uvs {
+            data.uvs.push(uv);
+        }
+    }
+    Ok(())
+}
+
+fn read_faces<R: BufRead>(body: &mut BodyReader<R>, element: &Element, data: &mut MeshData) -> Result<(), LoadError> {
+    let mut face = Vec::new();
+    for _ in 0..element.count {
+        body.start_row()?;
+        for property in &element.properties {
+            match property {
+                Property::List { name, count, item } if name == "vertex_indices" || name == "vertex_index" => {
+                    let n = body.read(*count)? as usize;
+                    face.clear();
+                    for _ in 0..n {
+                        let index = body.read(*item)?;
+                        if index < 0.0 {
+                            return Err(LoadError::Format {
+                                file: body.file.to_string(),
+                                message: format!("negative vertex index {index}"),
+                            });
+                        }
+                        face.push(index as usize);
+                    }
+                    for i in 1..n.saturating_sub(1) {
+                        data.indices.push([face[0], face[i], face[i + 1]]);
+                    }
+                }
+                _ => {
+                    body.read_property(property)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+struct BodyReader<'a, R> {
+    reader: R,
+    encoding: Encoding,
+    file: &'a str,
+    line: usize,
+    tokens: Vec<String>,
+    next_token: usize,
+}
+
+impl<R: BufRead> BodyReader<'_, R> {
+    /// Moves to the next element. In ASCII files every element sits on its
+    /// own line, so a short row is reported where it is rather than read
+    /// on into the next one.
+    fn start_row(&mut self) -> Result<(), LoadError> {
+        if self.encoding != Encoding::Ascii {
+            return Ok(());
+        }
+        loop {
+            let mut line = String::new();
+            self.line += 1;
+            if self.reader.read_line(&mut line)? == 0 {
+                let context = LineContext { file: self.file, line: self.line };
+                return Err(context.error("unexpected end of file"));
+            }
+            self.tokens = line.split_whitespace().map(str::to_string).collect();
+            self.next_token = 0;
+            if !self.tokens.is_empty() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Reads a property, returning the scalar value or the last list item.
+    fn read_property(&mut self, property: &Property) -> Result<f64, LoadError> {
+        match property {
+            Property::Scalar { ty, .. } => self.read(*ty),
+            Property::List { count, item, .. } => {
+                let n = self.read(*count)? as usize;
+                let mut value = 0.0;
+                for _ in 0..n {
+                    value = self.read(*item)?;
+                }
+                Ok(value)
+            }
+        }
+    }
+
+    fn read(&mut self, ty: ScalarType) -> Result<f64, LoadError> {
+        if self.encoding == Encoding::Ascii {
+            let token = self.next_ascii_token()?;
+            let context = LineContext { file: self.file, line: self.line };
+            return context.parse(Some(&token));
+        }
+
+        let mut buf = [0u8; 8];
+        let size = ty.size();
+        self.reader.read_exact(&mut buf[..size])?;
+        let big_endian = self.encoding == Encoding::BigEndian;
+        macro_rules! decode {
+            ($t:ty, $n:expr) => {{
+                let bytes: [u8; $n] = buf[..$n].try_into().unwrap();
+                if big_endian { <$t>::from_be_bytes(bytes) as f64 } else { <$t>::from_le_bytes(bytes) as f64 }
+            }};
+        }
+        Ok(match ty {
+            ScalarType::I8 => decode!(i8, 1),
+            ScalarType::U8 => decode!(u8, 1),
+            ScalarType::I16 => decode!(i16, 2),
+            ScalarType::U16 => decode!(u16, 2),
+            ScalarType::I32 => decode!(i32, 4),
+            ScalarType::U32 => decode!(u32, 4),
+            ScalarType::F32 => decode!(f32, 4),
+            ScalarType::F64 => decode!(f64, 8),
+        })
+    }
+
+    fn next_ascii_token(&mut self) -> Result<String, LoadError> {
+        if self.next_token >= self.tokens.len() {
+            let context = LineContext { file: self.file, line: self.line };
+            return Err(context.error("row has too few values"));
+        }
+        self.next_token += 1;
+        Ok(std::mem::take(&mut self.tokens[self.next_token - 1]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{color::Color, material::Lambertian, test_util::TempFile};
+
+    const VERTICES: [([f32; 5], [u8; 3]); 4] = [
+        ([0.0, 0.0, 0.0, 0.0, 0.0], [255, 0, 0]),
+        ([1.0, 0.0, 0.0, 1.0, 0.0], [0, 255, 0]),
+        ([1.0, 1.0, 0.5, 1.0, 1.0], [0, 0, 255]),
+        ([0.0, 1.0, -0.25, 0.0, 1.0], [255, 255, 255]),
+    ];
+    const FACES: [&[i32]; 2] = [&[0, 1, 2, 3], &[3, 2, 1]];
+
+    fn read_temp(name: &str, contents: &[u8]) -> Result<MeshData, LoadError> {
+        read_ply(TempFile::new(name, contents).path())
+    }
+
+    fn header(format: &str) -> String {
+        format!(
+            "ply\nformat {format} 1.0\ncomment test mesh\nelement vertex 4\nproperty float x\nproperty float y\n\
+             property float z\nproperty float u\nproperty float v\nproperty uchar red\nproperty uchar green\n\
+             property uchar blue\nelement face 2\nproperty list uchar int vertex_indices\nend_header\n"
+        )
+    }
+
+    fn ascii_mesh() -> Vec<u8> {
+        let mut text = header("ascii");
+        for (values, color) in VERTICES {
+            let values = values.iter().map(f32::to_string).collect::<Vec<_>>().join(" ");
+            text += &format!("{values} {} {} {}\n", color[0], color[1], color[2]);
+        }
+        for face in FACES {
+            let indices = face.iter().map(i32::to_string).collect::<Vec<_>>().join(" ");
+            text += &format!("{} {indices}\n", face.len());
+        }
+        text.into_bytes()
+    }
+
+    fn binary_mesh(big_endian: bool) -> Vec<u8> {
+        let format = if big_endian { "binary_big_endian" } else { "binary_little_endian" };
+        let mut bytes = header(format).into_bytes();
+        for (values, color) in VERTICES {
+            for value in values {
+                bytes.extend(if big_endian { value.to_be_bytes() } else { value.to_le_bytes() });
+            }
+            bytes.extend(color);
+        }
+        for face in FACES {
+            bytes.push(face.len() as u8);
+            for &index in face {
+                bytes.extend(if big_endian { index.to_be_bytes() } else { index.to_le_bytes() });
+            }
+        }
+        bytes
+    }
+
+    #[test]
+    fn encodings_read_the_same_mesh() {
+        let ascii = read_temp("ascii.ply", &ascii_mesh()).unwrap();
+        let little = read_temp("little.ply", &binary_mesh(false)).unwrap();
+        let big = read_temp("big.ply", &binary_mesh(true)).unwrap();
+        assert_eq!(ascii, little);
+        assert_eq!(ascii, big);
+
+        assert_eq!(ascii.indices, vec![[0, 1, 2], [0, 2, 3], [3, 2, 1]]);
+        assert_eq!(ascii.positions[3], Vector3::new(0.0, 1.0, -0.25));
+        assert_eq!(ascii.uvs[2], (1.0, 1.0));
+        assert!(ascii.normals.is_empty());
+    }
+
+    #[test]
+    fn truncated_ascii_row_reports_its_line() {
+        let source = "ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\nproperty float y\nproperty float z\n\
+                      end_header\n0 0 0\n1 0\n0 1 0\n";
+        let error = read_temp("truncated.ply", source.as_bytes()).err().unwrap();
+        assert!(matches!(error, LoadError::Parse { line: 9, .. }), "{error}");
+    }
+
+    #[test]
+    fn point_cloud_is_not_a_mesh() {
+        let source = "ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nproperty float y\nproperty float z\n\
+                      end_header\n0 0 0\n";
+        let file = TempFile::new("points.ply", source);
+        assert_eq!(read_ply(file.path()).unwrap().positions.len(), 1);
+        let material = Arc::new(Lambertian::new(Color::zeros()));
+        let error = load_ply(file.path(), material, Shading::Flat).err().unwrap();
+        assert!(matches!(error, LoadError::Format { .. }), "{error}");
+    }
+}
@@ -1,14 +1,50 @@
 
-use super::{color::Color, hittable::Hittable};
 use nalgebra::Vector3;
 
+/// Cone around a ray used to estimate its footprint for texture filtering.
+/// `width` is the cone's diameter at the ray origin and `spread` its angle.
+#[derive(Clone, Copy, Debug)]
+pub struct RayCone {
+    pub width: f64,
+    pub spread: f64,
+}
+
+impl RayCone {
+    /// The cone after travelling `distance` along the ray.
+    pub fn at(&self, distance: f64) -> RayCone {
+        RayCone { width: self.width + self.spread * distance, spread: self.spread }
+    }
+}
+
+#[derive(Clone)]
 pub struct Ray {
     a: Vector3<f64>,
     b: Vector3<f64>,
+    time: f64,
+    cone: Option<RayCone>,
 }
 impl Ray {
-    pub fn new(a: Vector3<f64>, b: Vector3<f64>) -> Self {
-        Ray { a, b }
+    pub fn new(a: Vector3<f64>, b: Vector3<f64>, time: f64) -> Self {
+        Ray { a, b, time, cone: None }
+    }
+
+    pub fn with_cone(mut self, cone: RayCone) -> Self {
+        self.cone = Some(cone);
+        self
+    }
+
+    /// Continues the cone of `parent` from its hit at `t`, for rays leaving a
+    /// specular surface. `extra_spread` widens it for glossy reflection.
+    pub fn with_cone_from(mut self, parent: &Ray, t: f64, extra_spread: f64) -> Self {
+        self.cone = parent.cone.map(|cone| {
+            let cone = cone.at(t * parent.b.magnitude());
+            RayCone { width: cone.width, spread: cone.spread + extra_spread }
+        });
+        self
+    }
+
+    pub fn cone(&self) -> Option<RayCone> {
+        self.cone
     }
 
     pub fn origin(&self) -> Vector3<f64> {
@@ -17,26 +53,10 @@ impl Ray {
     pub fn direction(&self) -> Vector3<f64> {
         self.b
     }
+    pub fn time(&self) -> f64 {
+        self.time
+    }
     pub fn at(&self, t: f64) -> Vector3<f64> {
         self.a + t * self.b
     }
-    pub fn color(&self, world: &dyn Hittable, depth: i32) -> Color {
-        if depth <= 0 {
-            return Color::new(0.0, 0.0, 0.0);
-        }
-
-        if let Some(hit) = world.hit(&self, 0.001, f64::MAX) {
-            if let Some((scattered, attenuation)) = hit.material.scatter(self, &hit) {
-                return attenuation.zip_map(&scattered.color(world, depth - 1), |l, r| l * r);
-            } else {
-                return Vector3::new(0.0, 0.0, 0.0);
-            }
-        } else {
-            let unit_direction = self.direction().normalize();
-            let t = 0.5 * (unit_direction.y + 1.0);
-            return (1.0 - t) * Color::new(1.0, 1.0, 1.0) + t * Color::new(0.5, 0.7, 1.0);
-        }
-    }
-
-    
 }
@@ -2,7 +2,14 @@ use std::sync::Arc;
 
 use nalgebra::Vector3;
 
-use super::{hittable::{Hittable, HitRecord}, material::Material, ray::Ray, aabb::AABB};
+use super::{
+    aabb::AABB,
+    hittable::{area_pdf, HitRecord, Hittable},
+    material::Material,
+    point::Point,
+    ray::Ray,
+    util::random_double_within,
+};
 
 pub enum Plane {
     YZ,
@@ -10,6 +17,16 @@ pub enum Plane {
     XY
 }
 
+impl Plane {
+    fn axes(&self) -> (usize, usize, usize) {
+        match self {
+            Plane::YZ => (0, 1, 2),
+            Plane::ZX => (1, 2, 0),
+            Plane::XY => (2, 0, 1)
+        }
+    }
+}
+
 pub struct AARect {
     plane: Plane,
     a0: f64,
@@ -28,11 +45,7 @@ impl AARect {
 
 impl Hittable for AARect {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        let (k_axis, a_axis, b_axis) = match &self.plane {
-            Plane::YZ => (0, 1, 2),
-            Plane::ZX => (1, 2, 0),
-            Plane::XY => (2, 0, 1)
-        };
+        let (k_axis, a_axis, b_axis) = self.plane.axes();
         let t = (self.k - ray.origin()[k_axis]) / ray.direction()[k_axis];
         if t < t_min || t > t_max {
             None
@@ -47,14 +60,79 @@ impl Hittable for AARect {
                 let p = ray.at(t);
                 let mut normal = Vector3::zeros();
                 normal[k_axis] = 1.0;
-                Some(HitRecord { t, u, v, p, normal, material: self.material.clone(), front_face:true })
+                let mut dpdu = Vector3::zeros();
+                dpdu[a_axis] = self.a1 - self.a0;
+                let mut dpdv = Vector3::zeros();
+                dpdv[b_axis] = self.b1 - self.b0;
+                Some(HitRecord { t, u, v, p, normal, material: self.material.clone(), front_face: true, dpdu, dpdv })
             }
         }
     }
 
     fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<AABB> {
-        let min = Vector3::new(self.a0, self.b0, self.k - 0.0001);
-        let max = Vector3::new(self.a1, self.b1, self.k + 0.0001);
+        let (k_axis, a_axis, b_axis) = self.plane.axes();
+        let mut min = Vector3::zeros();
+        let mut max = Vector3::zeros();
+        min[a_axis] = self.a0;
+        max[a_axis] = self.a1;
+        min[b_axis] = self.b0;
+        max[b_axis] = self.b1;
+        min[k_axis] = self.k - 0.0001;
+        max[k_axis] = self.k + 0.0001;
         Some(AABB { min, max })
     }
-}
\ No newline at end of file
+
+    fn pdf_value(&self, origin: &Point, direction: &Vector3<f64>) -> f64 {
+        match self.hit(&Ray::new(*origin, *direction, 0.0), 0.001, f64::MAX) {
+            Some(hit) => {
+                let area = (self.a1 - self.a0) * (self.b1 - self.b0);
+                area_pdf(direction, hit.t, &hit.normal, area)
+            }
+            None => 0.0,
+        }
+    }
+
+    fn random(&self, origin: &Point) -> Vector3<f64> {
+        let (k_axis, a_axis, b_axis) = self.plane.axes();
+        let mut point = Vector3::zeros();
+        point[a_axis] = random_double_within(self.a0, self.a1);
+        point[b_axis] = random_double_within(self.b0, self.b1);
+        point[k_axis] = self.k;
+        point - origin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{color::Color, material::Lambertian};
+
+    /// A 2 × 2 square at z = 2, centered on the z axis.
+    fn square() -> AARect {
+        AARect::new(Plane::XY, -1.0, 1.0, -1.0, 1.0, 2.0, Arc::new(Lambertian::new(Color::zeros())))
+    }
+
+    #[test]
+    fn pdf_value_matches_the_solid_angle_density() {
+        let rect = square();
+        let origin = Point::zeros();
+        assert!((rect.pdf_value(&origin, &Vector3::new(0.0, 0.0, 1.0)) - 1.0).abs() < 1e-12);
+
+        // Towards (0.5, 0, 2): distance² 4.25 and cosine 2 / √4.25.
+        let expected = 4.25 * 4.25f64.sqrt() / (2.0 * 4.0);
+        assert!((rect.pdf_value(&origin, &Vector3::new(0.5, 0.0, 2.0)) - expected).abs() < 1e-12);
+
+        assert_eq!(rect.pdf_value(&origin, &Vector3::new(1.0, 0.0, 1.0)), 0.0);
+        assert_eq!(rect.pdf_value(&origin, &Vector3::new(0.0, 0.0, -1.0)), 0.0);
+    }
+
+    #[test]
+    fn random_directions_have_a_density() {
+        let rect = square();
+        let origin = Point::new(0.3, -0.2, 0.5);
+        for _ in 0..100 {
+            let direction = rect.random(&origin);
+            assert!(rect.pdf_value(&origin, &direction) > 0.0, "{direction:?}");
+        }
+    }
+}
@@ -1,10 +1,15 @@
+use super::background::{Gradient, SolidColor};
+use super::bvh::BvhNode;
 use super::camera::Camera;
 use super::color::Color;
 use super::cube::Cube;
-use super::hittable::Hittable;
+use super::hittable::FlipNormals;
 use super::hittable_list::HittableList;
+use super::integrator::{Integrator, PathTracer};
 use super::material::*;
 use super::point::Point;
+use super::rect::{AARect, Plane};
+use super::scene::Scene;
 use super::sphere::Sphere;
 use super::util::*;
 use nalgebra::Vector3;
@@ -14,9 +19,9 @@ pub struct Renderer {
     pub image_width: i32,
     pub image_height: i32,
     pub samples_per_pixel: i32,
-    pub max_depth: i32,
+    pub integrator: Box<dyn Integrator>,
     pub cam: Camera,
-    pub world: Arc<dyn Hittable>,
+    pub scene: Scene,
 }
 
 impl Renderer {
@@ -29,7 +34,7 @@ impl Renderer {
                         let v = (line_number as f64 + random_double())
                             / ((self.image_height - 1) as f64);
                         let ray = self.cam.get_ray(u, v);
-                        ray.color(self.world.as_ref(), self.max_depth)
+                        self.integrator.radiance(&ray, &self.scene)
                     })
                     .sum();
                 self.pixel_to_rgb(&sampled_pixel)
@@ -44,7 +49,7 @@ impl Renderer {
                 let u = (x as f64 + random_double()) / ((self.image_width - 1) as f64);
                 let v = (y as f64 + random_double()) / ((self.image_height - 1) as f64);
                 let ray = self.cam.get_ray(u, v);
-                ray.color(self.world.as_ref(), self.max_depth)
+                self.integrator.radiance(&ray, &self.scene)
             })
             .sum();
         self.pixel_to_rgb(&sampled_pixel)
@@ -66,9 +71,7 @@ impl Renderer {
         let mut world = HittableList::default();
 
         // Earth
-        let material_ground = Arc::new(Lambertian {
-            albedo: Color::new(0.5, 0.5, 0.5),
-        });
+        let material_ground = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
         world.push(Sphere::new(
             Point::new(0.0, -1000.0, 0.0),
             1000.0,
@@ -82,20 +85,10 @@ impl Renderer {
         let material1 = Arc::new(Dielectric {
             index_of_refraction: 1.5,
         });
-        let material2 = Arc::new(Lambertian {
-            albedo: Color::new(0.4, 0.2, 0.1),
-        });
-        let material3 = Arc::new(Metal {
-            albedo: Color::new(0.7, 0.6, 0.5),
-            fuzz: 0.0,
-        });
-        let material4 = Arc::new(Metal {
-            albedo: Color::new(0.7, 0.1, 0.5),
-            fuzz: 0.4,
-        });
-        let axis_color = Arc::new(Lambertian {
-            albedo: Color::new(0.3, 0.3, 0.3),
-        });
+        let material2 = Arc::new(Lambertian::new(Color::new(0.4, 0.2, 0.1)));
+        let material3 = Arc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.0));
+        let material4 = Arc::new(Metal::new(Color::new(0.7, 0.1, 0.5), 0.4));
+        let axis_color = Arc::new(Lambertian::new(Color::new(0.3, 0.3, 0.3)));
         
         world.push(Sphere::new(Point::new(0.0, 1.0, 0.0), 1.0, material1.clone()));
         world.push(Sphere::new(Point::new(-4.0, 1.0, 0.0), 1.0, material2.clone()));
@@ -128,15 +121,83 @@ impl Renderer {
             aspect_ratio,
             aperture,
             dist_to_focus,
-        );
+        )
+        .with_ray_cones(image_height);
+
+        Renderer {
+            image_width,
+            image_height,
+            samples_per_pixel,
+            integrator: Box::new(PathTracer::new(max_depth)),
+            cam,
+            scene: Scene {
+                world: Arc::new(BvhNode::new(world, 0.0, 1.0)),
+                lights: HittableList::default(),
+                analytic_lights: Vec::new(),
+                background: Arc::new(Gradient {
+                    bottom: Color::new(1.0, 1.0, 1.0),
+                    top: Color::new(0.5, 0.7, 1.0),
+                    up: Vector3::new(0.0, 1.0, 0.0),
+                }),
+            },
+        }
+    }
+
+    pub fn cornell_box(image_width: i32, samples_per_pixel: i32, max_depth: i32) -> Self {
+        let mut world = HittableList::default();
+
+        let red = Arc::new(Lambertian::new(Color::new(0.65, 0.05, 0.05)));
+        let white = Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+        let green = Arc::new(Lambertian::new(Color::new(0.12, 0.45, 0.15)));
+        let light = Arc::new(DiffuseLight::new(Color::new(15.0, 15.0, 15.0)));
+
+        // Walls facing away from the inside of the box are flipped.
+        world.push(FlipNormals::new(AARect::new(Plane::YZ, 0.0, 555.0, 0.0, 555.0, 555.0, green)));
+        world.push(AARect::new(Plane::YZ, 0.0, 555.0, 0.0, 555.0, 0.0, red));
+        world.push(FlipNormals::new(AARect::new(Plane::ZX, 227.0, 332.0, 213.0, 343.0, 554.0, light.clone())));
+        let mut lights = HittableList::default();
+        lights.push(AARect::new(Plane::ZX, 227.0, 332.0, 213.0, 343.0, 554.0, light));
+        world.push(FlipNormals::new(AARect::new(Plane::ZX, 0.0, 555.0, 0.0, 555.0, 555.0, white.clone())));
+        world.push(AARect::new(Plane::ZX, 0.0, 555.0, 0.0, 555.0, 0.0, white.clone()));
+        world.push(FlipNormals::new(AARect::new(Plane::XY, 0.0, 555.0, 0.0, 555.0, 555.0, white.clone())));
+
+        world.push(Cube::new(Point::new(130.0, 0.0, 65.0), Point::new(295.0, 165.0, 230.0), white.clone()));
+        world.push(Cube::new(Point::new(265.0, 0.0, 295.0), Point::new(430.0, 330.0, 460.0), white.clone()));
+
+        let aspect_ratio = 1.0;
+        let image_height = (image_width as f64 / aspect_ratio) as i32;
+
+        let look_from = Point::new(278.0, 278.0, -800.0);
+        let look_at = Point::new(278.0, 278.0, 0.0);
+        let vup = Vector3::new(0.0, 1.0, 0.0);
+        let vfov = 40.0;
+        let aperture = 0.0;
+        let dist_to_focus = 10.0;
+        let cam = Camera::new(
+            look_from,
+            look_at,
+            vup,
+            vfov,
+            aspect_ratio,
+            aperture,
+            dist_to_focus,
+        )
+        .with_ray_cones(image_height);
 
         Renderer {
             image_width,
             image_height,
             samples_per_pixel,
-            max_depth,
+            integrator: Box::new(PathTracer::new(max_depth)),
             cam,
-            world: Arc::new(world),
+            scene: Scene {
+                world: Arc::new(BvhNode::new(world, 0.0, 1.0)),
+                lights,
+                analytic_lights: Vec::new(),
+                background: Arc::new(SolidColor {
+                    color: Color::new(0.0, 0.0, 0.0),
+                }),
+            },
         }
     }
 
@@ -156,12 +217,12 @@ impl Renderer {
                     if choose_material < 0.6 {
                         let rv = random_vector();
                         let albedo = Color::new(rv.x * rv.x, rv.y * rv.y, rv.z * rv.z);
-                        let sphere_material = Arc::new(Lambertian { albedo });
+                        let sphere_material = Arc::new(Lambertian::new(albedo));
                         world.push(Sphere::new(center, 0.2, sphere_material));
                     } else if choose_material < 0.9 {
                         let albedo = random_vector_within(0.5, 1.0);
                         let fuzz = random_double_within(0.0, 0.5);
-                        let sphere_material = Arc::new(Metal { albedo, fuzz });
+                        let sphere_material = Arc::new(Metal::new(albedo, fuzz));
                         world.push(Sphere::new(center, 0.2, sphere_material));
                     } else {
                         let sphere_material = Arc::new(Dielectric {
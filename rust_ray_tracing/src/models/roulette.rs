@@ -0,0 +1,43 @@
+use super::color::{luminance, Color};
+
+/// How the chance of a path surviving Russian roulette is chosen.
+#[derive(Clone, Copy, Debug)]
+pub enum SurvivalStrategy {
+    /// The largest channel of the path throughput.
+    Throughput,
+    /// The luminance of the path throughput.
+    Luminance,
+    /// A fixed probability.
+    Constant(f64),
+}
+
+/// Randomly ends paths after `min_depth` bounces. Surviving paths are
+/// weighted by the inverse of their survival probability, so the estimate
+/// stays unbiased while dim paths are cut short.
+#[derive(Clone, Copy, Debug)]
+pub struct RussianRoulette {
+    pub min_depth: i32,
+    pub strategy: SurvivalStrategy,
+}
+
+impl Default for RussianRoulette {
+    fn default() -> Self {
+        RussianRoulette { min_depth: 3, strategy: SurvivalStrategy::Throughput }
+    }
+}
+
+impl RussianRoulette {
+    /// Probability that a path with `throughput` continues after `bounce`
+    /// bounces.
+    pub fn survival_probability(&self, bounce: i32, throughput: &Color) -> f64 {
+        if bounce < self.min_depth {
+            return 1.0;
+        }
+        let probability = match self.strategy {
+            SurvivalStrategy::Throughput => throughput.max(),
+            SurvivalStrategy::Luminance => luminance(throughput),
+            SurvivalStrategy::Constant(probability) => probability,
+        };
+        probability.clamp(0.0, 1.0)
+    }
+}
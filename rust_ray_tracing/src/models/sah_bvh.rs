@@ -0,0 +1,360 @@
+use std::cmp::Ordering;
+
+use nalgebra::Vector3;
+
+use super::{
+    aabb::{surrounding_box, AABB},
+    hittable::{HitRecord, Hittable},
+    hittable_list::HittableList,
+    ray::Ray,
+};
+
+const BIN_COUNT: usize = 12;
+const MAX_LEAF_SIZE: usize = 4;
+// Past this depth nodes are split at the median, which keeps the traversal
+// stack below `STACK_SIZE` even for pathological inputs.
+const MAX_SAH_DEPTH: usize = 32;
+const STACK_SIZE: usize = 64;
+const TRAVERSAL_COST: f64 = 0.125;
+const INTERSECTION_COST: f64 = 1.0;
+
+/// Shape of a built BVH. `sah_cost` is the expected cost of a ray query in
+/// units of primitive intersections, so a plain `HittableList` of `n`
+/// objects costs `n`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BvhStats {
+    pub node_count: usize,
+    pub leaf_count: usize,
+    pub max_depth: usize,
+    pub sah_cost: f64,
+}
+
+#[derive(Clone, Copy)]
+struct LinearNode {
+    bbox: AABB,
+    // Leaves: first entry in `indices`. Interior nodes: index of the second
+    // child, the first one always follows its parent.
+    offset: usize,
+    // Zero for interior nodes.
+    count: usize,
+    axis: usize,
+}
+
+struct BuildPrimitive {
+    bbox: AABB,
+    centroid: Vector3<f64>,
+    index: usize,
+}
+
+/// Binned SAH hierarchy over primitive indices, stored depth-first in a
+/// single array. Owners keep the primitives and resolve leaf indices.
+pub(crate) struct LinearBvh {
+    nodes: Vec<LinearNode>,
+    indices: Vec<usize>,
+    stats: BvhStats,
+}
+
+impl LinearBvh {
+    pub(crate) fn new(bounds: &[AABB]) -> Self {
+        let mut primitives = bounds
+            .iter()
+            .enumerate()
+            .map(|(index, bbox)| BuildPrimitive { bbox: *bbox, centroid: bbox.centroid(), index })
+            .collect::<Vec<_>>();
+        let mut bvh = LinearBvh {
+            nodes: Vec::with_capacity(2 * primitives.len()),
+            indices: Vec::with_capacity(primitives.len()),
+            stats: BvhStats::default(),
+        };
+        if !primitives.is_empty() {
+            bvh.build(&mut primitives, 1);
+            bvh.stats.node_count = bvh.nodes.len();
+            bvh.stats.sah_cost = bvh.sah_cost();
+        }
+        bvh
+    }
+
+    pub(crate) fn stats(&self) -> BvhStats {
+        self.stats
+    }
+
+    pub(crate) fn bounds(&self) -> Option<AABB> {
+        self.nodes.first().map(|root| root.bbox)
+    }
+
+    /// Walks the tree front to back, calling `hit_primitive(index, t_min,
+    /// t_max)` for the primitives of every leaf the ray reaches and returning
+    /// the closest hit.
+    pub(crate) fn hit<F>(&self, ray: &Ray, t_min: f64, t_max: f64, mut hit_primitive: F) -> Option<HitRecord>
+    where
+        F: FnMut(usize, f64, f64) -> Option<HitRecord>,
+    {
+        if self.nodes.is_empty() {
+            return None;
+        }
+        let direction = ray.direction();
+        let mut closest_so_far = t_max;
+        let mut closest_hit = None;
+        let mut stack = [0usize; STACK_SIZE];
+        let mut stack_len = 0;
+        let mut current = 0;
+        loop {
+            let node = &self.nodes[current];
+            if node.bbox.hit(ray, t_min, closest_so_far) {
+                if node.count > 0 {
+                    for &index in &self.indices[node.offset..node.offset + node.count] {
+                        if let Some(hit) = hit_primitive(index, t_min, closest_so_far) {
+                            closest_so_far = hit.t;
+                            closest_hit = Some(hit);
+                        }
+                    }
+                } else {
+                    let (near, far) = if direction[node.axis] < 0.0 {
+                        (node.offset, current + 1)
+                    } else {
+                        (current + 1, node.offset)
+                    };
+                    stack[stack_len] = far;
+                    stack_len += 1;
+                    current = near;
+                    continue;
+                }
+            }
+            if stack_len == 0 {
+                break;
+            }
+            stack_len -= 1;
+            current = stack[stack_len];
+        }
+        closest_hit
+    }
+
+    fn build(&mut self, primitives: &mut [BuildPrimitive], depth: usize) -> usize {
+        let bbox = primitives[1..]
+            .iter()
+            .fold(primitives[0].bbox, |bbox, primitive| surrounding_box(&bbox, &primitive.bbox));
+        let node_index = self.nodes.len();
+        self.nodes.push(LinearNode { bbox, offset: 0, count: 0, axis: 0 });
+        self.stats.max_depth = self.stats.max_depth.max(depth);
+
+        if let Some((axis, mid)) = Self::split(primitives, &bbox, depth) {
+            let (left, right) = primitives.split_at_mut(mid);
+            self.build(left, depth + 1);
+            let second_child = self.build(right, depth + 1);
+            let node = &mut self.nodes[node_index];
+            node.offset = second_child;
+            node.axis = axis;
+        } else {
+            let node = &mut self.nodes[node_index];
+            node.offset = self.indices.len();
+            node.count = primitives.len();
+            self.indices.extend(primitives.iter().map(|primitive| primitive.index));
+            self.stats.leaf_count += 1;
+        }
+        node_index
+    }
+
+    /// Picks the split axis and the number of primitives going to the first
+    /// child, or `None` when a leaf is cheaper.
+    fn split(primitives: &mut [BuildPrimitive], bbox: &AABB, depth: usize) -> Option<(usize, usize)> {
+        let n = primitives.len();
+        if n == 1 {
+            return None;
+        }
+        let (min, max) = primitives.iter().fold(
+            (primitives[0].centroid, primitives[0].centroid),
+            |(min, max), primitive| (min.inf(&primitive.centroid), max.sup(&primitive.centroid)),
+        );
+        let extent = max - min;
+        let axis = extent.imax();
+        if extent[axis] <= 0.0 {
+            // Every centroid coincides, no split can separate them.
+            return None;
+        }
+        if n <= 2 || depth > MAX_SAH_DEPTH {
+            return Some((axis, Self::median_split(primitives, axis)));
+        }
+
+        let bin_of = |centroid: &Vector3<f64>| {
+            let b = (BIN_COUNT as f64 * (centroid[axis] - min[axis]) / extent[axis]) as usize;
+            b.min(BIN_COUNT - 1)
+        };
+        let mut bins: [(usize, Option<AABB>); BIN_COUNT] = [(0, None); BIN_COUNT];
+        for primitive in primitives.iter() {
+            let bin = &mut bins[bin_of(&primitive.centroid)];
+            bin.0 += 1;
+            bin.1 = Some(merge(bin.1, &primitive.bbox));
+        }
+
+        // costs[i] is the SAH cost of putting bins 0..=i in the first child.
+        let mut costs = [0.0; BIN_COUNT - 1];
+        let (mut count, mut bounds) = (0, None);
+        for (i, cost) in costs.iter_mut().enumerate() {
+            count += bins[i].0;
+            bounds = bins[i].1.map(|b| merge(bounds, &b)).or(bounds);
+            *cost = count as f64 * bounds.map_or(0.0, |b| b.surface_area());
+        }
+        let (mut count, mut bounds) = (0, None);
+        for i in (1..BIN_COUNT).rev() {
+            count += bins[i].0;
+            bounds = bins[i].1.map(|b| merge(bounds, &b)).or(bounds);
+            costs[i - 1] += count as f64 * bounds.map_or(0.0, |b| b.surface_area());
+        }
+        let (best_bin, best_cost) = costs
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+            .map(|(i, cost)| (i, *cost))
+            .unwrap();
+
+        let split_cost = TRAVERSAL_COST + INTERSECTION_COST * best_cost / bbox.surface_area().max(f64::MIN_POSITIVE);
+        let leaf_cost = INTERSECTION_COST * n as f64;
+        if n <= MAX_LEAF_SIZE && split_cost >= leaf_cost {
+            return None;
+        }
+
+        let mut mid = 0;
+        for i in 0..n {
+            if bin_of(&primitives[i].centroid) <= best_bin {
+                primitives.swap(i, mid);
+                mid += 1;
+            }
+        }
+        if mid == 0 || mid == n {
+            mid = Self::median_split(primitives, axis);
+        }
+        Some((axis, mid))
+    }
+
+    fn median_split(primitives: &mut [BuildPrimitive], axis: usize) -> usize {
+        let mid = primitives.len() / 2;
+        primitives.select_nth_unstable_by(mid, |a, b| {
+            a.centroid[axis].partial_cmp(&b.centroid[axis]).unwrap_or(Ordering::Equal)
+        });
+        mid
+    }
+
+    fn sah_cost(&self) -> f64 {
+        let root_area = self.nodes[0].bbox.surface_area().max(f64::MIN_POSITIVE);
+        self.nodes
+            .iter()
+            .map(|node| {
+                let cost = if node.count == 0 {
+                    TRAVERSAL_COST
+                } else {
+                    INTERSECTION_COST * node.count as f64
+                };
+                cost * node.bbox.surface_area() / root_area
+            })
+            .sum()
+    }
+}
+
+fn merge(bounds: Option<AABB>, bbox: &AABB) -> AABB {
+    bounds.map_or(*bbox, |bounds| surrounding_box(&bounds, bbox))
+}
+
+/// BVH over the objects of a `HittableList` built with the binned surface
+/// area heuristic. Nodes live in one flat array and are traversed nearest
+/// child first, with no allocation per interior node.
+pub struct SahBvh {
+    primitives: Vec<Box<dyn Hittable>>,
+    tree: LinearBvh,
+}
+
+impl SahBvh {
+    pub fn new(list: HittableList, time0: f64, time1: f64) -> Self {
+        let primitives = list.into_objects();
+        let bounds = primitives
+            .iter()
+            .map(|object| {
+                object
+                    .bounding_box(time0, time1)
+                    .expect("No bounding box in SahBvh constructor")
+            })
+            .collect::<Vec<_>>();
+        SahBvh { tree: LinearBvh::new(&bounds), primitives }
+    }
+
+    pub fn stats(&self) -> BvhStats {
+        self.tree.stats()
+    }
+}
+
+impl Hittable for SahBvh {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.tree.hit(ray, t_min, t_max, |index, t_min, t_max| {
+            self.primitives[index].hit(ray, t_min, t_max)
+        })
+    }
+
+    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<AABB> {
+        self.tree.bounds()
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        // Never report a hit so the walk reaches every leaf along the ray.
+        let mut transmittance = 1.0;
+        self.tree.hit(ray, t_min, t_max, |index, t_min, t_max| {
+            if transmittance > 0.0 {
+                transmittance *= self.primitives[index].transmittance(ray, t_min, t_max);
+            }
+            None
+        });
+        transmittance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::models::{
+        color::Color,
+        material::{Lambertian, Material},
+        sphere::Sphere,
+        triangle::Triangle,
+        util::{random_unit_vector, random_vector_within},
+    };
+
+    /// Two lists holding the same random spheres and triangles.
+    fn random_lists() -> (HittableList, HittableList) {
+        let material: Arc<dyn Material> = Arc::new(Lambertian::new(Color::zeros()));
+        let centers = (0..50).map(|_| random_vector_within(-2.0, 2.0)).collect::<Vec<_>>();
+        let triangles = (0..200)
+            .map(|_| {
+                let v0 = random_vector_within(-2.0, 2.0);
+                [v0, v0 + random_vector_within(-0.5, 0.5), v0 + random_vector_within(-0.5, 0.5)]
+            })
+            .collect::<Vec<_>>();
+        let list = || {
+            let mut list = HittableList::default();
+            for &center in &centers {
+                list.push(Sphere::new(center, 0.2, material.clone()));
+            }
+            for &[v0, v1, v2] in &triangles {
+                list.push(Triangle::new(v0, v1, v2, material.clone()));
+            }
+            list
+        };
+        (list(), list())
+    }
+
+    #[test]
+    fn closest_hits_match_a_brute_force_search() {
+        let (brute_force, list) = random_lists();
+        let bvh = SahBvh::new(list, 0.0, 1.0);
+        let mut hits = 0;
+        for _ in 0..1000 {
+            let ray = Ray::new(random_vector_within(-3.0, 3.0), random_unit_vector(), 0.0);
+            for t_max in [1.5, f64::INFINITY] {
+                let expected = brute_force.hit(&ray, 0.001, t_max).map(|hit| (hit.t, hit.p));
+                let found = bvh.hit(&ray, 0.001, t_max).map(|hit| (hit.t, hit.p));
+                assert_eq!(found, expected, "{:?} {:?} up to {t_max}", ray.origin(), ray.direction());
+                hits += expected.is_some() as usize;
+            }
+        }
+        assert!(hits > 100, "only {hits} rays hit anything");
+    }
+}
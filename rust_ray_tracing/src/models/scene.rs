@@ -0,0 +1,17 @@
+use std::sync::Arc;
+
+use super::{background::Background, hittable::Hittable, hittable_list::HittableList, light::Light};
+
+/// Everything an integrator needs to shade camera rays.
+pub struct Scene {
+    pub world: Arc<dyn Hittable>,
+    /// Emissive shapes sampled explicitly by integrators, copies of light
+    /// geometry in `world` with the same material. Direct light sampling
+    /// reads the emission of the copy it hits, while light found by
+    /// scattered rays comes from `world`, so a copy with another material
+    /// biases the image.
+    pub lights: HittableList,
+    /// Point, spot and directional lights that have no geometry.
+    pub analytic_lights: Vec<Box<dyn Light>>,
+    pub background: Arc<dyn Background>,
+}
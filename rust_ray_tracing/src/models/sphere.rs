@@ -1,7 +1,10 @@
 use super::aabb::AABB;
 use super::hittable::{HitRecord, Hittable};
 use super::material::Material;
+use super::onb::Onb;
+use super::point::Point;
 use super::ray::Ray;
+use super::util::random_double;
 use nalgebra::Vector3;
 use std::sync::Arc;
 
@@ -23,45 +26,96 @@ impl Sphere {
 
 impl Hittable for Sphere {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        let oc = ray.origin() - self.center;
-        let a = ray.direction().magnitude_squared();
-        let half_b = oc.dot(&ray.direction());
-        let c = oc.magnitude_squared() - self.radius * self.radius;
-
-        let discriminant = half_b.powi(2) - a * c;
-        if discriminant < 0.0 {
-            return None;
-        };
-        let sqrtd = discriminant.sqrt();
-
-        let mut root = (-half_b - sqrtd) / a;
-        if root < t_min || t_max < root {
-            root = (-half_b + sqrtd) / a;
-            if root < t_min || t_max < root {
-                return None;
-            }
-        }
-        let normal = (ray.at(root) - self.center) / self.radius;
-        let (u, v) = get_sphere_uv(&normal);
-        let mut hit_record = HitRecord {
-            t: root,
-            p: ray.at(root),
-            u,
-            v,
-            normal,
-            front_face: true,
-            material: self.material.clone(),
-        };
-        hit_record.set_face_normal(&ray);
-        return Some(hit_record);
+        hit_sphere(&self.center, self.radius, &self.material, ray, t_min, t_max)
     }
 
-    fn bounding_box(&self, time0: f64, time1: f64) -> Option<AABB> {
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<AABB> {
         Some(AABB::new(
             self.center - Vector3::new(self.radius, self.radius, self.radius),
             self.center + Vector3::new(self.radius, self.radius, self.radius),
         ))
     }
+
+    /// Samples the cone of directions the sphere subtends from outside, or
+    /// every direction from inside.
+    fn pdf_value(&self, origin: &Point, direction: &Vector3<f64>) -> f64 {
+        if self.hit(&Ray::new(*origin, *direction, 0.0), 0.001, f64::MAX).is_none() {
+            return 0.0;
+        }
+        let distance_squared = (self.center - origin).magnitude_squared();
+        if distance_squared <= self.radius * self.radius {
+            return 1.0 / (4.0 * std::f64::consts::PI);
+        }
+        let cos_theta_max = (1.0 - self.radius * self.radius / distance_squared).sqrt();
+        1.0 / (2.0 * std::f64::consts::PI * (1.0 - cos_theta_max))
+    }
+
+    fn random(&self, origin: &Point) -> Vector3<f64> {
+        let direction = self.center - origin;
+        let distance_squared = direction.magnitude_squared();
+        if distance_squared <= self.radius * self.radius {
+            let z = 1.0 - 2.0 * random_double();
+            let phi = 2.0 * std::f64::consts::PI * random_double();
+            let r = (1.0 - z * z).sqrt();
+            return Vector3::new(r * phi.cos(), r * phi.sin(), z);
+        }
+        Onb::from_w(&direction).local(&random_to_sphere(self.radius, distance_squared))
+    }
+}
+
+/// Direction around +z within the cone subtended by a sphere of `radius`
+/// at squared distance `distance_squared`, uniform over its solid angle.
+fn random_to_sphere(radius: f64, distance_squared: f64) -> Vector3<f64> {
+    let r1 = random_double();
+    let r2 = random_double();
+    let z = 1.0 + r2 * ((1.0 - radius * radius / distance_squared).sqrt() - 1.0);
+    let phi = 2.0 * std::f64::consts::PI * r1;
+    let r = (1.0 - z * z).sqrt();
+    Vector3::new(phi.cos() * r, phi.sin() * r, z)
+}
+
+pub(crate) fn hit_sphere(
+    center: &Vector3<f64>,
+    radius: f64,
+    material: &Arc<dyn Material>,
+    ray: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord> {
+    let oc = ray.origin() - center;
+    let a = ray.direction().magnitude_squared();
+    let half_b = oc.dot(&ray.direction());
+    let c = oc.magnitude_squared() - radius * radius;
+
+    let discriminant = half_b.powi(2) - a * c;
+    if discriminant < 0.0 {
+        return None;
+    };
+    let sqrtd = discriminant.sqrt();
+
+    let mut root = (-half_b - sqrtd) / a;
+    if root < t_min || t_max < root {
+        root = (-half_b + sqrtd) / a;
+        if root < t_min || t_max < root {
+            return None;
+        }
+    }
+    let normal = (ray.at(root) - center) / radius;
+    let (u, v) = get_sphere_uv(&normal);
+    let (dpdu, dpdv) = sphere_derivatives(&normal, radius);
+    let mut hit_record = HitRecord {
+        t: root,
+        p: ray.at(root),
+        u,
+        v,
+        normal,
+        front_face: true,
+        material: material.clone(),
+        dpdu,
+        dpdv,
+    };
+    hit_record.set_face_normal(ray);
+    Some(hit_record)
 }
 
 fn get_sphere_uv(p: &Vector3<f64>) -> (f64, f64) {
@@ -71,3 +125,14 @@ fn get_sphere_uv(p: &Vector3<f64>) -> (f64, f64) {
     let v = (theta + std::f64::consts::FRAC_PI_2) / std::f64::consts::PI;
     (u, v)
 }
+
+/// Derivatives of the point on the sphere with respect to the (u, v) of
+/// `get_sphere_uv`, which are degenerate at the poles.
+fn sphere_derivatives(p: &Vector3<f64>, radius: f64) -> (Vector3<f64>, Vector3<f64>) {
+    let phi = p.z.atan2(p.x);
+    let theta = p.y.clamp(-1.0, 1.0).asin();
+    let pi = std::f64::consts::PI;
+    let dpdu = 2.0 * pi * radius * Vector3::new(theta.cos() * phi.sin(), 0.0, -theta.cos() * phi.cos());
+    let dpdv = pi * radius * Vector3::new(-theta.sin() * phi.cos(), theta.cos(), -theta.sin() * phi.sin());
+    (dpdu, dpdv)
+}
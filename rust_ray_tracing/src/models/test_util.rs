@@ -0,0 +1,33 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// File in the system temp directory for loader tests, removed on drop.
+pub(crate) struct TempFile {
+    path: PathBuf,
+}
+
+impl TempFile {
+    /// `name` is prefixed with the process id so parallel test runs do not
+    /// collide, and is what other test files should use to reference it.
+    pub(crate) fn new(name: &str, contents: impl AsRef<[u8]>) -> Self {
+        let path = std::env::temp_dir().join(format!("rust_ray_tracing_{}_{name}", std::process::id()));
+        fs::write(&path, contents).unwrap();
+        TempFile { path }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn file_name(&self) -> &str {
+        self.path.file_name().unwrap().to_str().unwrap()
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
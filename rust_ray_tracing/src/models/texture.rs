@@ -0,0 +1,326 @@
+use std::{path::Path, sync::Arc};
+
+use image::ImageError;
+
+use super::{
+    color::Color,
+    load_error::LoadError,
+    noise::{Perlin, Worley},
+    point::Point,
+};
+
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f64, v: f64, p: &Point) -> Color;
+
+    /// Average of the texture over `footprint` around (u, v). Textures that
+    /// cannot alias just return their value at the center.
+    fn filtered_value(&self, u: f64, v: f64, p: &Point, _footprint: &Footprint) -> Color {
+        self.value(u, v, p)
+    }
+}
+
+/// Area of texture space seen through a ray cone, given as the (u, v)
+/// offsets spanning the long and short axes of the ellipse it covers.
+#[derive(Clone, Copy, Debug)]
+pub struct Footprint {
+    pub major: (f64, f64),
+    pub minor: (f64, f64),
+}
+
+pub struct ConstantTexture {
+    pub color: Color,
+}
+
+impl Texture for ConstantTexture {
+    fn value(&self, _u: f64, _v: f64, _p: &Point) -> Color {
+        self.color
+    }
+}
+
+/// Alternates between two textures on a 3D grid of cubes with side `scale`.
+pub struct CheckerTexture {
+    pub even: Arc<dyn Texture>,
+    pub odd: Arc<dyn Texture>,
+    pub scale: f64,
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, u: f64, v: f64, p: &Point) -> Color {
+        let cell = p.map(|c| (c / self.scale).floor() as i64);
+        if (cell.x + cell.y + cell.z).rem_euclid(2) == 0 {
+            self.even.value(u, v, p)
+        } else {
+            self.odd.value(u, v, p)
+        }
+    }
+
+    fn filtered_value(&self, u: f64, v: f64, p: &Point, footprint: &Footprint) -> Color {
+        let cell = p.map(|c| (c / self.scale).floor() as i64);
+        if (cell.x + cell.y + cell.z).rem_euclid(2) == 0 {
+            self.even.filtered_value(u, v, p, footprint)
+        } else {
+            self.odd.filtered_value(u, v, p, footprint)
+        }
+    }
+}
+
+/// How texture coordinates outside [0, 1] are mapped back onto the image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WrapMode {
+    Repeat,
+    Clamp,
+    Mirror,
+}
+
+impl WrapMode {
+    fn apply(self, i: i64, size: usize) -> usize {
+        let size = size as i64;
+        let i = match self {
+            WrapMode::Repeat => i.rem_euclid(size),
+            WrapMode::Clamp => i.clamp(0, size - 1),
+            WrapMode::Mirror => {
+                let i = i.rem_euclid(2 * size);
+                if i < size { i } else { 2 * size - 1 - i }
+            }
+        };
+        i as usize
+    }
+}
+
+/// Linear RGB image mapped over (u, v) with v = 1 at the top row and
+/// bilinear filtering between texel centers. Coordinates are transformed
+/// by `scale` then `offset` before `wrap` is applied.
+///
+/// Filtered lookups pick levels of a mip pyramid from the footprint's
+/// short axis and average up to `MAX_ANISOTROPY` probes along its long axis.
+pub struct ImageTexture {
+    levels: Vec<MipLevel>,
+    pub wrap: WrapMode,
+    pub scale: (f64, f64),
+    pub offset: (f64, f64),
+}
+
+const MAX_ANISOTROPY: f64 = 8.0;
+
+struct MipLevel {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+impl MipLevel {
+    /// Half resolution level where each texel averages a 2x2 block.
+    fn downsample(&self) -> MipLevel {
+        let width = (self.width / 2).max(1);
+        let height = (self.height / 2).max(1);
+        let mut pixels = Vec::with_capacity(width * height);
+        for j in 0..height {
+            for i in 0..width {
+                let mut sum = Color::zeros();
+                for (di, dj) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    let x = (2 * i + di).min(self.width - 1);
+                    let y = (2 * j + dj).min(self.height - 1);
+                    sum += self.pixels[y * self.width + x];
+                }
+                pixels.push(sum / 4.0);
+            }
+        }
+        MipLevel { width, height, pixels }
+    }
+
+    fn bilinear(&self, wrap: WrapMode, u: f64, v: f64) -> Color {
+        // Texel centers sit at half-integer image coordinates.
+        let x = u * self.width as f64 - 0.5;
+        let y = (1.0 - v) * self.height as f64 - 0.5;
+        let (i, j) = (x.floor(), y.floor());
+        let (s, t) = (x - i, y - j);
+        let (i, j) = (i as i64, j as i64);
+        let texel = |i: i64, j: i64| self.pixels[wrap.apply(j, self.height) * self.width + wrap.apply(i, self.width)];
+        let top = (1.0 - s) * texel(i, j) + s * texel(i + 1, j);
+        let bottom = (1.0 - s) * texel(i, j + 1) + s * texel(i + 1, j + 1);
+        (1.0 - t) * top + t * bottom
+    }
+}
+
+impl ImageTexture {
+    /// `pixels` are stored row by row, top row first.
+    pub fn new(width: usize, height: usize, pixels: Vec<Color>) -> Self {
+        assert!(width > 0 && height > 0, "An image texture needs at least one pixel");
+        assert_eq!(pixels.len(), width * height, "Expected width * height pixels");
+        let mut levels = vec![MipLevel { width, height, pixels }];
+        while let Some(last) = levels.last().filter(|level| level.width > 1 || level.height > 1) {
+            levels.push(last.downsample());
+        }
+        ImageTexture {
+            levels,
+            wrap: WrapMode::Repeat,
+            scale: (1.0, 1.0),
+            offset: (0.0, 0.0),
+        }
+    }
+
+    /// Loads a PNG, JPEG or PPM image, decoding its sRGB values to linear.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        Self::load_with(path.as_ref(), srgb_to_linear)
+    }
+
+    /// Loads an image holding data rather than colors, such as a normal or
+    /// height map, keeping its values as stored.
+    pub fn load_linear(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        Self::load_with(path.as_ref(), |c| c)
+    }
+
+    fn load_with(path: &Path, decode: fn(f64) -> f64) -> Result<Self, LoadError> {
+        let image = image::open(path).map_err(|error| match error {
+            ImageError::IoError(error) => LoadError::Io(error),
+            error => LoadError::Format {
+                file: path.display().to_string(),
+                message: error.to_string(),
+            },
+        })?;
+        let image = image.to_rgb32f();
+        if image.width() == 0 || image.height() == 0 {
+            return Err(LoadError::Format {
+                file: path.display().to_string(),
+                message: "image has no pixels".to_string(),
+            });
+        }
+        let pixels = image
+            .pixels()
+            .map(|pixel| Color::new(pixel[0] as f64, pixel[1] as f64, pixel[2] as f64).map(decode))
+            .collect();
+        Ok(Self::new(image.width() as usize, image.height() as usize, pixels))
+    }
+
+    /// Blends the two mip levels around the fractional level `lod`.
+    fn trilinear(&self, u: f64, v: f64, lod: f64) -> Color {
+        let level = lod.floor() as usize;
+        let fine = self.levels[level].bilinear(self.wrap, u, v);
+        match self.levels.get(level + 1) {
+            Some(coarse) => {
+                let t = lod - level as f64;
+                (1.0 - t) * fine + t * coarse.bilinear(self.wrap, u, v)
+            }
+            None => fine,
+        }
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: &Point) -> Color {
+        let u = u * self.scale.0 + self.offset.0;
+        let v = v * self.scale.1 + self.offset.1;
+        self.levels[0].bilinear(self.wrap, u, v)
+    }
+
+    fn filtered_value(&self, u: f64, v: f64, _p: &Point, footprint: &Footprint) -> Color {
+        let u = u * self.scale.0 + self.offset.0;
+        let v = v * self.scale.1 + self.offset.1;
+        let scaled = |(du, dv): (f64, f64)| (du * self.scale.0, dv * self.scale.1);
+        let (width, height) = (self.levels[0].width as f64, self.levels[0].height as f64);
+        let texels = |(du, dv): (f64, f64)| (du * width).hypot(dv * height);
+
+        let (mut major, mut minor) = (scaled(footprint.major), scaled(footprint.minor));
+        if texels(minor) > texels(major) {
+            std::mem::swap(&mut major, &mut minor);
+        }
+        let probes = (texels(major) / texels(minor).max(1e-8)).ceil().clamp(1.0, MAX_ANISOTROPY);
+        let lod = (texels(major) / probes).max(1e-8).log2().clamp(0.0, (self.levels.len() - 1) as f64);
+
+        let mut sum = Color::zeros();
+        for k in 0..probes as usize {
+            let offset = (k as f64 + 0.5) / probes - 0.5;
+            sum += self.trilinear(u + offset * major.0, v + offset * major.1, lod);
+        }
+        sum / probes
+    }
+}
+
+fn srgb_to_linear(c: f64) -> f64 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Veined marble: stripes along z bent by Perlin turbulence.
+pub struct MarbleTexture {
+    noise: Perlin,
+    scale: f64,
+    base: Color,
+    vein: Color,
+}
+
+impl MarbleTexture {
+    pub fn new(seed: u64, scale: f64, base: Color, vein: Color) -> Self {
+        MarbleTexture { noise: Perlin::new(seed), scale, base, vein }
+    }
+}
+
+impl Texture for MarbleTexture {
+    fn value(&self, _u: f64, _v: f64, p: &Point) -> Color {
+        let p = self.scale * p;
+        let t = 0.5 * (1.0 + (p.z + 10.0 * self.noise.turbulence(&p, 7)).sin());
+        t * self.base + (1.0 - t) * self.vein
+    }
+}
+
+/// Wood grain: growth rings around the y axis distorted by turbulence.
+pub struct WoodTexture {
+    noise: Perlin,
+    scale: f64,
+    light: Color,
+    dark: Color,
+}
+
+impl WoodTexture {
+    pub fn new(seed: u64, scale: f64, light: Color, dark: Color) -> Self {
+        WoodTexture { noise: Perlin::new(seed), scale, light, dark }
+    }
+}
+
+impl Texture for WoodTexture {
+    fn value(&self, _u: f64, _v: f64, p: &Point) -> Color {
+        let p = self.scale * p;
+        let rings = (p.x * p.x + p.z * p.z).sqrt() + 2.0 * self.noise.turbulence(&p, 4);
+        let t = rings.fract().powi(3);
+        (1.0 - t) * self.light + t * self.dark
+    }
+}
+
+/// Voronoi cells from Worley noise, `edge` colored along the cell borders.
+pub struct CellularTexture {
+    noise: Worley,
+    scale: f64,
+    cell: Color,
+    edge: Color,
+}
+
+impl CellularTexture {
+    pub fn new(seed: u64, scale: f64, cell: Color, edge: Color) -> Self {
+        CellularTexture { noise: Worley::new(seed), scale, cell, edge }
+    }
+}
+
+impl Texture for CellularTexture {
+    fn value(&self, _u: f64, _v: f64, p: &Point) -> Color {
+        let (nearest, second) = self.noise.distances(&(self.scale * p));
+        // Borders are where the two nearest feature points are equally far.
+        let t = ((second - nearest) / 0.1).clamp(0.0, 1.0);
+        let t = t * t * (3.0 - 2.0 * t);
+        t * self.cell + (1.0 - t) * self.edge
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::test_util::TempFile;
+
+    #[test]
+    fn empty_image_is_a_format_error() {
+        let file = TempFile::new("empty.ppm", "P3\n0 0\n255\n");
+        assert!(matches!(ImageTexture::load(file.path()), Err(LoadError::Format { .. })));
+    }
+}
@@ -0,0 +1,140 @@
+use nalgebra::{Matrix3, Matrix4, Rotation3, Unit, Vector3};
+
+use super::{
+    aabb::AABB,
+    hittable::{HitRecord, Hittable},
+    point::Point,
+    ray::{Ray, RayCone},
+};
+
+/// Places a hittable with an affine matrix, e.g. to rotate a `Cube` or
+/// scale a mesh. Rays are brought into the object's space instead of
+/// moving the object.
+pub struct Transform<H: Hittable> {
+    hittable: H,
+    linear: Matrix3<f64>,
+    translation: Vector3<f64>,
+    inverse_linear: Matrix3<f64>,
+}
+
+impl<H: Hittable> Transform<H> {
+    /// `matrix` must be affine and invertible, its bottom row is ignored.
+    pub fn new(hittable: H, matrix: Matrix4<f64>) -> Self {
+        let linear: Matrix3<f64> = matrix.fixed_slice::<3, 3>(0, 0).into();
+        let translation = Vector3::new(matrix[(0, 3)], matrix[(1, 3)], matrix[(2, 3)]);
+        let inverse_linear = linear.try_inverse().expect("Transform matrix must be invertible");
+        Transform { hittable, linear, translation, inverse_linear }
+    }
+
+    pub fn translate(hittable: H, offset: Vector3<f64>) -> Self {
+        Self::new(hittable, Matrix4::new_translation(&offset))
+    }
+
+    /// Rotates by `degrees` around `axis` through the origin.
+    pub fn rotate(hittable: H, axis: Vector3<f64>, degrees: f64) -> Self {
+        let rotation = Rotation3::from_axis_angle(&Unit::new_normalize(axis), degrees.to_radians());
+        Self::new(hittable, rotation.to_homogeneous())
+    }
+
+    pub fn scale(hittable: H, factors: Vector3<f64>) -> Self {
+        Self::new(hittable, Matrix4::new_nonuniform_scaling(&factors))
+    }
+
+    fn to_local(&self, point: &Point) -> Point {
+        self.inverse_linear * (point - self.translation)
+    }
+
+    /// The direction is not normalized, so t is the same in both spaces.
+    fn to_local_ray(&self, ray: &Ray) -> Ray {
+        let local_ray = Ray::new(self.to_local(&ray.origin()), self.inverse_linear * ray.direction(), ray.time());
+        match ray.cone() {
+            Some(cone) => {
+                let scale = self.inverse_linear.determinant().abs().cbrt();
+                local_ray.with_cone(RayCone { width: cone.width * scale, spread: cone.spread })
+            }
+            None => local_ray,
+        }
+    }
+}
+
+impl<H: Hittable> Hittable for Transform<H> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.hittable.hit(&self.to_local_ray(ray), t_min, t_max).map(|mut hit| {
+            hit.p = self.linear * hit.p + self.translation;
+            // Normals transform with the inverse transpose so they stay
+            // perpendicular to the surface under non-uniform scaling.
+            hit.normal = self.inverse_linear.tr_mul(&hit.normal).normalize();
+            hit.dpdu = self.linear * hit.dpdu;
+            hit.dpdv = self.linear * hit.dpdv;
+            hit
+        })
+    }
+
+    fn bounding_box(&self, t0: f64, t1: f64) -> Option<AABB> {
+        let local = self.hittable.bounding_box(t0, t1)?;
+        let mut min = Vector3::repeat(f64::INFINITY);
+        let mut max = Vector3::repeat(f64::NEG_INFINITY);
+        for corner in 0..8 {
+            let point = Vector3::new(
+                if corner & 1 == 0 { local.min.x } else { local.max.x },
+                if corner & 2 == 0 { local.min.y } else { local.max.y },
+                if corner & 4 == 0 { local.min.z } else { local.max.z },
+            );
+            let point = self.linear * point + self.translation;
+            min = min.inf(&point);
+            max = max.sup(&point);
+        }
+        Some(AABB { min, max })
+    }
+
+    fn pdf_value(&self, origin: &Point, direction: &Vector3<f64>) -> f64 {
+        // Solid angle is distorted by the mapping between the spaces, by
+        // |det A| / |A w|^3 for unit directions w and A the inverse matrix.
+        let local_direction = self.inverse_linear * direction.normalize();
+        let length = local_direction.magnitude();
+        self.hittable.pdf_value(&self.to_local(origin), &local_direction)
+            * self.inverse_linear.determinant().abs()
+            / (length * length * length)
+    }
+
+    fn random(&self, origin: &Point) -> Vector3<f64> {
+        self.linear * self.hittable.random(&self.to_local(origin))
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        self.hittable.transmittance(&self.to_local_ray(ray), t_min, t_max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::models::{
+        color::Color,
+        material::{Lambertian, Material},
+        rect::{AARect, Plane},
+    };
+
+    #[test]
+    fn scaled_rect_has_the_pdf_of_the_larger_rect() {
+        let material: Arc<dyn Material> = Arc::new(Lambertian::new(Color::zeros()));
+        let small = AARect::new(Plane::XY, -0.5, 0.5, -0.5, 0.5, 1.0, material.clone());
+        let scaled = Transform::scale(small, Vector3::new(2.0, 3.0, 2.0));
+        let large = AARect::new(Plane::XY, -1.0, 1.0, -1.5, 1.5, 2.0, material);
+
+        let origins = [Point::zeros(), Point::new(0.4, -0.7, -1.0), Point::new(-2.0, 0.5, 0.5)];
+        let directions = [Vector3::new(0.0, 0.0, 1.0), Vector3::new(0.3, -0.4, 1.0), Vector3::new(1.0, 0.5, 0.8)];
+        let mut hits = 0;
+        for origin in &origins {
+            for direction in &directions {
+                let expected = large.pdf_value(origin, direction);
+                let pdf = scaled.pdf_value(origin, direction);
+                hits += (expected > 0.0) as usize;
+                assert!((pdf - expected).abs() < 1e-9 * expected.max(1.0), "{origin:?} {direction:?}: {pdf} vs {expected}");
+            }
+        }
+        assert!(hits >= 4, "only {hits} directions reach the rect");
+    }
+}
@@ -0,0 +1,238 @@
+use std::sync::Arc;
+
+use nalgebra::Vector3;
+
+use super::{
+    aabb::AABB,
+    hittable::{area_pdf, HitRecord, Hittable},
+    material::Material,
+    point::Point,
+    ray::Ray,
+    util::random_double,
+};
+
+const DEFAULT_UVS: [(f64, f64); 3] = [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)];
+
+pub struct Triangle {
+    vertices: [Point; 3],
+    normals: Option<[Vector3<f64>; 3]>,
+    uvs: [(f64, f64); 3],
+    material: Arc<dyn Material>,
+}
+
+impl Triangle {
+    pub fn new(v0: Point, v1: Point, v2: Point, material: Arc<dyn Material>) -> Self {
+        Triangle { vertices: [v0, v1, v2], normals: None, uvs: DEFAULT_UVS, material }
+    }
+
+    /// Triangle with per-vertex shading normals and texture coordinates.
+    /// Without normals it is flat shaded, without UVs `u`/`v` are the
+    /// barycentric coordinates of the hit.
+    pub fn with_vertex_data(
+        vertices: [Point; 3],
+        normals: Option<[Vector3<f64>; 3]>,
+        uvs: Option<[(f64, f64); 3]>,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        Triangle { vertices, normals, uvs: uvs.unwrap_or(DEFAULT_UVS), material }
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let [v0, v1, v2] = &self.vertices;
+        let (t, barycentric) = intersect(ray, v0, v1, v2, t_min, t_max)?;
+        Some(hit_record(ray, t, barycentric, &self.vertices, self.normals.as_ref(), &self.uvs, &self.material))
+    }
+
+    fn bounding_box(&self, _t0: f64, _t1: f64) -> Option<AABB> {
+        let [v0, v1, v2] = &self.vertices;
+        Some(triangle_bounds(v0, v1, v2))
+    }
+
+    fn pdf_value(&self, origin: &Point, direction: &Vector3<f64>) -> f64 {
+        let [v0, v1, v2] = &self.vertices;
+        match intersect(&Ray::new(*origin, *direction, 0.0), v0, v1, v2, 0.001, f64::MAX) {
+            Some((t, _)) => {
+                let normal = (v1 - v0).cross(&(v2 - v0));
+                area_pdf(direction, t, &normal, 0.5 * normal.magnitude())
+            }
+            None => 0.0,
+        }
+    }
+
+    fn random(&self, origin: &Point) -> Vector3<f64> {
+        let [v0, v1, v2] = &self.vertices;
+        sample_triangle(v0, v1, v2) - origin
+    }
+}
+
+/// Point picked uniformly over the area of a triangle.
+pub(crate) fn sample_triangle(v0: &Point, v1: &Point, v2: &Point) -> Point {
+    let r1 = random_double().sqrt();
+    let r2 = random_double();
+    (1.0 - r1) * v0 + r1 * (1.0 - r2) * v1 + r1 * r2 * v2
+}
+
+pub(crate) fn triangle_bounds(v0: &Point, v1: &Point, v2: &Point) -> AABB {
+    let mut min = v0.inf(v1).inf(v2);
+    let mut max = v0.sup(v1).sup(v2);
+    for a in 0..=2 {
+        if max[a] - min[a] < 0.0001 {
+            min[a] -= 0.0001;
+            max[a] += 0.0001;
+        }
+    }
+    AABB { min, max }
+}
+
+/// Watertight ray/triangle intersection (Woop, Benthin and Wald 2013), so
+/// rays never slip through the shared edge of two mesh triangles. Returns
+/// the ray parameter and the barycentric weights of `v0`, `v1` and `v2`.
+pub(crate) fn intersect(ray: &Ray, v0: &Point, v1: &Point, v2: &Point, t_min: f64, t_max: f64) -> Option<(f64, [f64; 3])> {
+    let direction = ray.direction();
+    let origin = ray.origin();
+
+    // Shear the triangle into a space where the ray runs along +z.
+    let kz = direction.iamax();
+    let (mut kx, mut ky) = ((kz + 1) % 3, (kz + 2) % 3);
+    if direction[kz] < 0.0 {
+        std::mem::swap(&mut kx, &mut ky);
+    }
+    let sx = direction[kx] / direction[kz];
+    let sy = direction[ky] / direction[kz];
+    let sz = 1.0 / direction[kz];
+
+    let a = v0 - origin;
+    let b = v1 - origin;
+    let c = v2 - origin;
+    let (ax, ay) = (a[kx] - sx * a[kz], a[ky] - sy * a[kz]);
+    let (bx, by) = (b[kx] - sx * b[kz], b[ky] - sy * b[kz]);
+    let (cx, cy) = (c[kx] - sx * c[kz], c[ky] - sy * c[kz]);
+
+    let u = cx * by - cy * bx;
+    let v = ax * cy - ay * cx;
+    let w = bx * ay - by * ax;
+    if (u < 0.0 || v < 0.0 || w < 0.0) && (u > 0.0 || v > 0.0 || w > 0.0) {
+        return None;
+    }
+    let det = u + v + w;
+    if det == 0.0 {
+        return None;
+    }
+
+    let t_scaled = u * sz * a[kz] + v * sz * b[kz] + w * sz * c[kz];
+    let t = t_scaled / det;
+    if t < t_min || t > t_max {
+        return None;
+    }
+    Some((t, [u / det, v / det, w / det]))
+}
+
+pub(crate) fn hit_record(
+    ray: &Ray,
+    t: f64,
+    barycentric: [f64; 3],
+    vertices: &[Point; 3],
+    normals: Option<&[Vector3<f64>; 3]>,
+    uvs: &[(f64, f64); 3],
+    material: &Arc<dyn Material>,
+) -> HitRecord {
+    let [b0, b1, b2] = barycentric;
+    let [v0, v1, v2] = vertices;
+    let p = b0 * v0 + b1 * v1 + b2 * v2;
+    let u = b0 * uvs[0].0 + b1 * uvs[1].0 + b2 * uvs[2].0;
+    let v = b0 * uvs[0].1 + b1 * uvs[1].1 + b2 * uvs[2].1;
+
+    // Which side was hit is decided by the geometric normal; an interpolated
+    // shading normal is only flipped onto that side.
+    let geometric_normal = (v1 - v0).cross(&(v2 - v0)).normalize();
+    let front_face = ray.direction().dot(&geometric_normal) < 0.0;
+    let mut normal = normals
+        .map(|[n0, n1, n2]| (b0 * n0 + b1 * n1 + b2 * n2).normalize())
+        .filter(|n| n.iter().all(|c| c.is_finite()))
+        .unwrap_or(geometric_normal);
+    if normal.dot(&geometric_normal) < 0.0 {
+        normal = -normal;
+    }
+    if !front_face {
+        normal = -normal;
+    }
+    let (dpdu, dpdv) = triangle_derivatives(vertices, uvs);
+    HitRecord { t, p, u, v, normal, front_face, material: material.clone(), dpdu, dpdv }
+}
+
+/// Derivatives of the position over the triangle with respect to its texture
+/// coordinates, zero when the coordinates are degenerate.
+fn triangle_derivatives(vertices: &[Point; 3], uvs: &[(f64, f64); 3]) -> (Vector3<f64>, Vector3<f64>) {
+    let (e1, e2) = (vertices[1] - vertices[0], vertices[2] - vertices[0]);
+    let (du1, dv1) = (uvs[1].0 - uvs[0].0, uvs[1].1 - uvs[0].1);
+    let (du2, dv2) = (uvs[2].0 - uvs[0].0, uvs[2].1 - uvs[0].1);
+    let determinant = du1 * dv2 - dv1 * du2;
+    if determinant.abs() < 1e-12 {
+        return (Vector3::zeros(), Vector3::zeros());
+    }
+    ((dv2 * e1 - dv1 * e2) / determinant, (du1 * e2 - du2 * e1) / determinant)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{color::Color, material::Lambertian};
+
+    fn triangle(v0: [f64; 3], v1: [f64; 3], v2: [f64; 3]) -> Triangle {
+        let material = Arc::new(Lambertian::new(Color::zeros()));
+        Triangle::new(Point::from(v0), Point::from(v1), Point::from(v2), material)
+    }
+
+    /// Casts a ray from (x, y, 5) towards `direction`.
+    fn hits(triangles: &[Triangle], x: f64, y: f64, direction: Vector3<f64>) -> bool {
+        let ray = Ray::new(Point::new(x, y, 5.0), direction, 0.0);
+        triangles.iter().any(|triangle| triangle.hit(&ray, 0.001, f64::INFINITY).is_some())
+    }
+
+    #[test]
+    fn shared_edges_and_vertices_are_watertight() {
+        // A unit quad split along its diagonal, and four triangles around
+        // the origin, sharing the origin and the axes.
+        let quad = [
+            triangle([0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]),
+            triangle([0.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]),
+        ];
+        let fan = [
+            triangle([0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            triangle([0.0, 0.0, 0.0], [0.0, 1.0, 0.0], [-1.0, 0.0, 0.0]),
+            triangle([0.0, 0.0, 0.0], [-1.0, 0.0, 0.0], [0.0, -1.0, 0.0]),
+            triangle([0.0, 0.0, 0.0], [0.0, -1.0, 0.0], [1.0, 0.0, 0.0]),
+        ];
+        for direction in [Vector3::new(0.0, 0.0, -1.0), Vector3::new(0.3, -0.1, -1.0), Vector3::new(-0.7, 0.2, -0.9)] {
+            let offset = direction * (-5.0 / direction.z);
+            for i in 1..100 {
+                let s = i as f64 / 100.0;
+                assert!(hits(&quad, s - offset.x, s - offset.y, direction), "diagonal at {s}");
+                assert!(hits(&fan, s - offset.x, -offset.y, direction), "x axis at {s}");
+                assert!(hits(&fan, -offset.x, -s - offset.y, direction), "y axis at {s}");
+            }
+            assert!(hits(&fan, -offset.x, -offset.y, direction), "shared vertex");
+        }
+    }
+
+    #[test]
+    fn rays_beside_behind_or_along_the_triangle_miss() {
+        let triangle = [triangle([0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0])];
+        assert!(hits(&triangle, 0.2, 0.2, Vector3::new(0.0, 0.0, -1.0)));
+        assert!(!hits(&triangle, 0.6, 0.6, Vector3::new(0.0, 0.0, -1.0)));
+        assert!(!hits(&triangle, -0.1, 0.2, Vector3::new(0.0, 0.0, -1.0)));
+        assert!(!hits(&triangle, 0.2, 0.2, Vector3::new(0.0, 0.0, 1.0)));
+        assert!(!hits(&triangle, 0.2, 0.2, Vector3::new(1.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn hits_are_clipped_to_the_ray_interval() {
+        let triangle = triangle([0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]);
+        let ray = Ray::new(Point::new(0.2, 0.2, 5.0), Vector3::new(0.0, 0.0, -1.0), 0.0);
+        assert_eq!(triangle.hit(&ray, 4.0, 6.0).map(|hit| hit.t), Some(5.0));
+        assert!(triangle.hit(&ray, 0.001, 4.9).is_none());
+        assert!(triangle.hit(&ray, 5.1, f64::INFINITY).is_none());
+    }
+}
@@ -56,3 +56,19 @@ pub fn random_vector_in_unit_disk() -> Vector3<f64> {
     }
 }
 
+/// Direction in the hemisphere around +z with density cos(theta) / pi.
+pub fn random_cosine_direction() -> Vector3<f64> {
+    let r1 = random_double();
+    let r2 = random_double();
+    let phi = 2.0 * std::f64::consts::PI * r1;
+    let z = (1.0 - r2).sqrt();
+    Vector3::new(phi.cos() * r2.sqrt(), phi.sin() * r2.sqrt(), z)
+}
+
+/// Direction distributed uniformly over the unit sphere.
+pub fn random_unit_vector() -> Vector3<f64> {
+    let z = random_double_within(-1.0, 1.0);
+    let phi = 2.0 * std::f64::consts::PI * random_double();
+    let r = (1.0 - z * z).sqrt();
+    Vector3::new(r * phi.cos(), r * phi.sin(), z)
+}
@@ -0,0 +1,214 @@
+use std::{fs, path::Path, sync::Arc};
+
+use nalgebra::Vector3;
+
+use super::{
+    aabb::AABB,
+    color::Color,
+    hittable::{HitRecord, Hittable},
+    load_error::LoadError,
+    material::Material,
+    point::Point,
+    ray::Ray,
+    texture::Texture,
+    util::random_double,
+};
+
+/// Scalar field sampled on a regular grid of voxels filling `bounds`, such
+/// as the density or temperature of a smoke simulation.
+pub struct VoxelGrid {
+    resolution: [usize; 3],
+    values: Vec<f32>,
+    max_value: f64,
+    pub bounds: AABB,
+}
+
+impl VoxelGrid {
+    /// Builds a grid from values stored with x varying fastest, then y, then z.
+    ///
+    /// Panics if a dimension is zero or `values` does not hold exactly
+    /// nx * ny * nz values. `load_raw` reports both as errors instead.
+    pub fn new(resolution: [usize; 3], values: Vec<f32>, bounds: AABB) -> Self {
+        assert!(resolution.iter().all(|&n| n > 0), "A voxel grid needs at least one voxel");
+        assert_eq!(Some(values.len()), value_count(resolution), "Expected nx * ny * nz values");
+        let max_value = values.iter().fold(0.0f32, |max, &value| max.max(value)) as f64;
+        VoxelGrid { resolution, values, max_value, bounds }
+    }
+
+    /// Loads a headerless file of little-endian `f32` values in the order
+    /// `new` expects.
+    pub fn load_raw(path: impl AsRef<Path>, resolution: [usize; 3], bounds: AABB) -> Result<Self, LoadError> {
+        let path = path.as_ref();
+        let bytes = fs::read(path)?;
+        let expected = value_count(resolution).and_then(|count| count.checked_mul(4)).filter(|&size| size > 0);
+        if expected != Some(bytes.len()) {
+            let message = match expected {
+                Some(size) => format!("expected {size} bytes for a {resolution:?} grid, found {}", bytes.len()),
+                None => format!("a {resolution:?} grid is empty or too large"),
+            };
+            return Err(LoadError::Format { file: path.display().to_string(), message });
+        }
+        let values = bytes
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+        Ok(Self::new(resolution, values, bounds))
+    }
+
+    pub fn max_value(&self) -> f64 {
+        self.max_value
+    }
+
+    /// Trilinear interpolation between voxel centers, zero outside `bounds`.
+    pub fn value(&self, p: &Point) -> f64 {
+        let extent = self.bounds.max - self.bounds.min;
+        let local = (p - self.bounds.min).component_div(&extent);
+        if local.iter().any(|&x| !(0.0..=1.0).contains(&x)) {
+            return 0.0;
+        }
+
+        let mut corners = [[0usize; 2]; 3];
+        let mut weights = [0.0; 3];
+        for axis in 0..3 {
+            let n = self.resolution[axis];
+            let x = (local[axis] * n as f64 - 0.5).clamp(0.0, (n - 1) as f64);
+            let i = (x as usize).min(n - 1);
+            corners[axis] = [i, (i + 1).min(n - 1)];
+            weights[axis] = x - i as f64;
+        }
+
+        let mut value = 0.0;
+        for (dz, wz) in [(0, 1.0 - weights[2]), (1, weights[2])] {
+            for (dy, wy) in [(0, 1.0 - weights[1]), (1, weights[1])] {
+                for (dx, wx) in [(0, 1.0 - weights[0]), (1, weights[0])] {
+                    let (x, y, z) = (corners[0][dx], corners[1][dy], corners[2][dz]);
+                    let index = (z * self.resolution[1] + y) * self.resolution[0] + x;
+                    value += wx * wy * wz * self.values[index] as f64;
+                }
+            }
+        }
+        value
+    }
+}
+
+/// Number of voxels in a grid of `resolution`, `None` on overflow.
+fn value_count(resolution: [usize; 3]) -> Option<usize> {
+    resolution.iter().try_fold(1usize, |count, &n| count.checked_mul(n))
+}
+
+/// `color` scaled by a voxel grid, e.g. the emission of a temperature field.
+pub struct GridTexture {
+    pub grid: Arc<VoxelGrid>,
+    pub color: Color,
+}
+
+impl Texture for GridTexture {
+    fn value(&self, _u: f64, _v: f64, p: &Point) -> Color {
+        self.grid.value(p) * self.color
+    }
+}
+
+/// Participating medium whose density follows a voxel grid, scaled by
+/// `density_scale`. Collisions are found with delta tracking against the
+/// grid's maximum, and shadow rays are weighted by a ratio tracking
+/// estimate of the transmittance rather than stopped at the first one.
+pub struct GridMedium {
+    density: Arc<VoxelGrid>,
+    density_scale: f64,
+    phase_function: Arc<dyn Material>,
+}
+
+impl GridMedium {
+    pub fn new(density: Arc<VoxelGrid>, density_scale: f64, phase_function: Arc<dyn Material>) -> Self {
+        GridMedium { density, density_scale, phase_function }
+    }
+}
+
+impl Hittable for GridMedium {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let max_density = self.density_scale * self.density.max_value();
+        if max_density <= 0.0 {
+            return None;
+        }
+        let (t_enter, t_exit) = self.density.bounds.clip(ray, t_min, t_max)?;
+
+        // Step through a homogeneous medium of the maximum density and keep
+        // each tentative collision with probability density / max_density.
+        let step = 1.0 / (max_density * ray.direction().magnitude());
+        let mut t = t_enter;
+        loop {
+            t -= step * (1.0 - random_double()).ln();
+            if t >= t_exit {
+                return None;
+            }
+            let p = ray.at(t);
+            if random_double() * max_density < self.density_scale * self.density.value(&p) {
+                // Normal and orientation mean nothing inside a volume.
+                return Some(HitRecord {
+                    t,
+                    p,
+                    u: 0.0,
+                    v: 0.0,
+                    normal: Vector3::x(),
+                    front_face: true,
+                    material: self.phase_function.clone(),
+                    dpdu: Vector3::zeros(),
+                    dpdv: Vector3::zeros(),
+                });
+            }
+        }
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<AABB> {
+        Some(self.density.bounds)
+    }
+
+    /// Ratio tracking: takes the same tentative collisions as `hit`, but
+    /// multiplies in the chance of passing each one instead of sampling it.
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        let max_density = self.density_scale * self.density.max_value();
+        let (t_enter, t_exit) = match self.density.bounds.clip(ray, t_min, t_max) {
+            Some(span) if max_density > 0.0 => span,
+            _ => return 1.0,
+        };
+
+        let step = 1.0 / (max_density * ray.direction().magnitude());
+        let mut transmittance = 1.0;
+        let mut t = t_enter;
+        loop {
+            t -= step * (1.0 - random_double()).ln();
+            if t >= t_exit {
+                return transmittance;
+            }
+            transmittance *= 1.0 - self.density_scale * self.density.value(&ray.at(t)) / max_density;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::test_util::TempFile;
+
+    fn unit_bounds() -> AABB {
+        AABB::new(Vector3::zeros(), Vector3::new(1.0, 1.0, 1.0))
+    }
+
+    #[test]
+    fn loads_little_endian_raw_values() {
+        let bytes = (0..8).flat_map(|i| (i as f32).to_le_bytes()).collect::<Vec<_>>();
+        let file = TempFile::new("grid.raw", bytes);
+        let grid = VoxelGrid::load_raw(file.path(), [2, 2, 2], unit_bounds()).unwrap();
+        assert_eq!(grid.max_value(), 7.0);
+        assert_eq!(grid.value(&Point::new(0.5, 0.5, 0.5)), 3.5);
+    }
+
+    #[test]
+    fn rejects_mismatched_and_overflowing_sizes() {
+        let file = TempFile::new("short.raw", [0u8; 12]);
+        for resolution in [[2, 2, 1], [0, 3, 1], [usize::MAX / 2, 3, 1]] {
+            let result = VoxelGrid::load_raw(file.path(), resolution, unit_bounds());
+            assert!(matches!(result, Err(LoadError::Format { .. })), "{resolution:?}");
+        }
+    }
+}